@@ -1 +1,2 @@
-pub mod myui;
\ No newline at end of file
+pub mod myui;
+pub mod viewport;
\ No newline at end of file
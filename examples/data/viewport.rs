@@ -0,0 +1,114 @@
+use std::ffi::c_uint;
+use std::mem;
+
+use gl33::*;
+use gl33::global_loader::*;
+use egui_glfw_gl2::gui::{GuiContext, RenderTarget, RenderTargetOptions, UiComponent};
+use egui_glfw_gl2::shader::Shader;
+
+// Draws the triangle into a render target that follows the size of its egui window
+pub struct Viewport {
+    target: Option<RenderTarget>,
+    triangle: Option<Triangle>,
+}
+
+impl UiComponent for Viewport {
+    fn init(&mut self, gui_ctx: &mut GuiContext) {
+        let options = RenderTargetOptions {
+            samples: 4,
+            ..Default::default()
+        };
+        self.target = Some(RenderTarget::new(&mut gui_ctx.gui_render, [320, 240], options));
+        self.triangle = Some(Triangle::new());
+    }
+
+    fn update(&mut self, gui_ctx: &mut GuiContext) {
+        let (Some(target), Some(triangle)) = (self.target.as_mut(), self.triangle.as_ref()) else {
+            return;
+        };
+        let egui_ctx = gui_ctx.egui_ctx.clone();
+        egui::Window::new("Viewport")
+            .resizable(true)
+            .default_size([320.0, 240.0])
+            .show(&egui_ctx, |ui| {
+                target.show(ui, &mut gui_ctx.gui_render);
+            });
+        target.draw(|_size| unsafe {
+            glClearColor(0.1, 0.2, 0.3, 1.0);
+            glClear(GL_COLOR_BUFFER_BIT | GL_DEPTH_BUFFER_BIT);
+            triangle.draw();
+        });
+    }
+}
+
+impl Viewport {
+    pub fn new() -> Self {
+        Self {
+            target: None,
+            triangle: None,
+        }
+    }
+}
+
+const TRIANGLE_SHADER_SRC: &str = "
+#type vertex
+#version 330 core
+layout (location=0) in vec2 aPos;
+
+void main() {
+    gl_Position = vec4(aPos, 0.0, 1.0);
+}
+
+#type fragment
+#version 330 core
+out vec4 outColor;
+
+void main() {
+    outColor = vec4(1.0, 1.0, 1.0, 1.0);
+}
+";
+
+static TRIANGLE_VERTICES: [f32; 6] = [0.0, 0.5, 0.5, -0.5, -0.5, -0.5];
+
+struct Triangle {
+    shader: Shader,
+    vao: c_uint,
+    vbo: c_uint,
+}
+
+impl Triangle {
+    fn new() -> Self {
+        let shader = Shader::from_source("triangle.glsl", TRIANGLE_SHADER_SRC).expect("Error: the triangle shader failed to build");
+        let (mut vao, mut vbo) = (0, 0);
+        unsafe {
+            glGenVertexArrays(1, &mut vao);
+            glBindVertexArray(vao);
+            glGenBuffers(1, &mut vbo);
+            glBindBuffer(GL_ARRAY_BUFFER, vbo);
+            glBufferData(GL_ARRAY_BUFFER, mem::size_of_val(&TRIANGLE_VERTICES) as _, TRIANGLE_VERTICES.as_ptr().cast(), GL_STATIC_DRAW);
+            glEnableVertexAttribArray(0);
+            glVertexAttribPointer(0, 2, GL_FLOAT, GL_FALSE.0 as _, 0, core::ptr::null());
+            glBindVertexArray(0);
+        }
+        Triangle { shader, vao, vbo }
+    }
+
+    fn draw(&self) {
+        self.shader.attach();
+        unsafe {
+            glBindVertexArray(self.vao);
+            glDrawArrays(GL_TRIANGLES, 0, 3);
+            glBindVertexArray(0);
+        }
+        self.shader.detach();
+    }
+}
+
+impl Drop for Triangle {
+    fn drop(&mut self) {
+        unsafe {
+            glDeleteBuffers(1, &self.vbo);
+            glDeleteVertexArrays(1, &self.vao);
+        }
+    }
+}
@@ -1,10 +1,12 @@
 use egui_glfw_gl2::glfw_window::GlfwWindow;
 use crate::data::myui::MyUI;
+use crate::data::viewport::Viewport;
 
 mod data;
 
 fn main() {
     let mut window = GlfwWindow::new(1280, 720, "test");
     window.add_ui_component(Box::new(MyUI::new(320, 192)));
+    window.add_ui_component(Box::new(Viewport::new()));
     window.run();
 }
\ No newline at end of file
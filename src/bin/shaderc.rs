@@ -0,0 +1,350 @@
+// Checks shader files without an OpenGL context, e.g. in a pre-commit hook on a build machine.
+//
+// Every stage is loaded like `Shader::new` does and then parsed and validated by naga's GLSL front end.
+// naga only reads Vulkan GLSL, so OpenGL-only declarations are rewritten first without moving any line.
+
+use std::collections::{BTreeMap, HashMap};
+use std::process::ExitCode;
+
+use naga::front::glsl::{Frontend, Options};
+use naga::valid::{Capabilities, ValidationFlags, Validator};
+use naga::{AddressSpace, ArraySize, Module, ScalarKind, TypeInner};
+
+use egui_glfw_gl2::shader::{load_shader_stages, PreprocessedSource, ShaderStage};
+
+const USAGE: &str = "Usage: egui_glfw_gl2-shaderc [--uniforms] [-D NAME[=VALUE]]... FILE...
+
+Validates shader files in the #type format and prints errors as file:line.
+  --uniforms        list the uniforms of every valid file
+  -D NAME[=VALUE]   add a #define to every stage, like ShaderSet::add_file_with_defines";
+
+fn main() -> ExitCode {
+    let mut list_uniforms = false;
+    let mut defines = Vec::new();
+    let mut files = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--uniforms" => list_uniforms = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            "-D" => match args.next() {
+                Some(define) => defines.push(define),
+                None => return usage_error("-D needs a NAME")
+            },
+            _ if arg.starts_with("-D") => defines.push(arg[2..].to_string()),
+            _ if arg.starts_with('-') => return usage_error(&format!("unknown option {}", arg)),
+            _ => files.push(arg)
+        }
+    }
+    if files.is_empty() {
+        return usage_error("no shader files given");
+    }
+
+    let defines: Vec<(&str, &str)> = defines.iter().map(|define| define.split_once('=').unwrap_or((define, ""))).collect();
+    let mut failed = 0;
+    for file in &files {
+        match check_file(file, &defines) {
+            Ok(uniforms) => {
+                println!("{}: ok", file);
+                if list_uniforms {
+                    for declaration in uniforms.values() {
+                        println!("    uniform {}", declaration);
+                    }
+                }
+            }
+            Err(errors) => {
+                failed += 1;
+                errors.iter().for_each(|error| eprintln!("{}", error));
+            }
+        }
+    }
+
+    if failed > 0 {
+        eprintln!("{} of {} shader files failed", failed, files.len());
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+fn usage_error(message: &str) -> ExitCode {
+    eprintln!("Error: {}\n\n{}", message, USAGE);
+    ExitCode::from(2)
+}
+
+// the uniform declarations of all stages by name, or one message per error
+fn check_file(file_path: &str, defines: &[(&str, &str)]) -> Result<BTreeMap<String, String>, Vec<String>> {
+    let stages = load_shader_stages(file_path, defines).map_err(|err| vec![err.to_string()])?;
+    let mut uniforms = BTreeMap::new();
+    let mut errors = Vec::new();
+    for (stage, source) in &stages {
+        let naga_stage = match stage {
+            ShaderStage::Vertex => naga::ShaderStage::Vertex,
+            ShaderStage::Fragment => naga::ShaderStage::Fragment,
+            ShaderStage::Compute => naga::ShaderStage::Compute,
+            ShaderStage::Geometry => {
+                eprintln!("{}: warning: naga has no geometry shaders, the {} stage is not checked", file_path, stage);
+                continue;
+            }
+        };
+        match check_stage(*stage, naga_stage, source) {
+            Ok(stage_uniforms) => uniforms.extend(stage_uniforms),
+            Err(stage_errors) => errors.extend(stage_errors.into_iter().map(|(line, message)| {
+                let location = line.and_then(|line| source.source_line(line)).map_or(file_path.to_string(), |(file, line)| format!("{}:{}", file, line));
+                format!("{}: {} shader: {}", location, stage, message)
+            }))
+        }
+    }
+    if errors.is_empty() { Ok(uniforms) } else { Err(errors) }
+}
+
+// a message and the line of the preprocessed source it points at
+type StageError = (Option<usize>, String);
+
+fn check_stage(stage: ShaderStage, naga_stage: naga::ShaderStage, source: &PreprocessedSource) -> Result<Vec<(String, String)>, Vec<StageError>> {
+    let vulkan = VulkanGlsl::new(&source.source, stage);
+    let module = Frontend::default()
+        .parse(&Options::from(naga_stage), &vulkan.source)
+        .map_err(|errors| {
+            errors
+                .iter()
+                .map(|error| (vulkan.source_line(error.meta.location(&vulkan.source).line_number as usize), error.kind.to_string()))
+                .collect::<Vec<_>>()
+        })?;
+
+    // bindings and layouts are assigned by the OpenGL linker, don't check them
+    let flags = ValidationFlags::all() - ValidationFlags::BINDINGS - ValidationFlags::STRUCT_LAYOUTS;
+    Validator::new(flags, Capabilities::all()).validate(&module).map_err(|error| {
+        let line = error.location(&vulkan.source).and_then(|location| vulkan.source_line(location.line_number as usize));
+        vec![(line, error_chain(error.as_inner()))]
+    })?;
+
+    Ok(vulkan.uniforms(&module))
+}
+
+// naga errors say where and their sources say what
+fn error_chain(error: &dyn std::error::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(error) = source {
+        message += &format!(": {}", error);
+        source = error.source();
+    }
+    message
+}
+
+/// A stage rewritten into the Vulkan GLSL naga reads.
+///
+/// Every line stays where it was, only a few lines of `#define`s are added after `#version`.
+struct VulkanGlsl {
+    source: String,
+    // the added lines are source lines header_start + 1 ..= header_start + header_len
+    header_start: usize,
+    header_len: usize,
+    // GLSL type of every combined sampler, split into a `<name>_texture` and a `<name>_sampler`
+    samplers: HashMap<String, String>
+}
+
+impl VulkanGlsl {
+    fn new(source: &str, stage: ShaderStage) -> Self {
+        let lines: Vec<&str> = source.lines().collect();
+        let version = lines.iter().position(|line| line.trim_start().starts_with("#version"));
+        let body_start = version.map_or(0, |index| index + 1);
+
+        let mut binding = 0;
+        let mut samplers = HashMap::new();
+        let body: Vec<String> = lines[body_start..]
+            .iter()
+            .map(|line| rewrite_uniform(line, &mut binding, &mut samplers).unwrap_or_else(|| line.to_string()))
+            .collect();
+
+        let mut header = vec![
+            "#version 450 core".to_string(),
+            "#define gl_VertexID gl_VertexIndex".to_string(),
+            "#define gl_InstanceID gl_InstanceIndex".to_string(),
+            // function-like, texture2D and textureCube are type names in Vulkan GLSL
+            "#define texture2D(sampler, coords) texture(sampler, coords)".to_string(),
+            "#define texture2DLod(sampler, coords, lod) textureLod(sampler, coords, lod)".to_string(),
+            "#define textureCube(sampler, coords) texture(sampler, coords)".to_string()
+        ];
+        if stage == ShaderStage::Fragment && source.contains("gl_FragColor") {
+            header.push("layout(location = 0) out vec4 fragColor_;".to_string());
+            header.push("#define gl_FragColor fragColor_".to_string());
+        }
+        let mut sampler_names: Vec<&String> = samplers.keys().collect();
+        sampler_names.sort();
+        for name in sampler_names {
+            header.push(format!("#define {0} {1}({0}_texture, {0}_sampler)", name, samplers[name]));
+        }
+
+        // the #version line is replaced by the first header line
+        let header_start = version.unwrap_or(0);
+        let header_len = header.len() - version.map_or(0, |_| 1);
+        let source = lines[..header_start].iter().map(|line| line.to_string()).chain(header).chain(body).collect::<Vec<_>>().join("\n");
+        VulkanGlsl { source, header_start, header_len, samplers }
+    }
+
+    // the line of the original source, None for the added lines
+    fn source_line(&self, line: usize) -> Option<usize> {
+        if line <= self.header_start + 1 {
+            Some(line)
+        } else if line > self.header_start + 1 + self.header_len {
+            Some(line - self.header_len)
+        } else {
+            None
+        }
+    }
+
+    // (name, GLSL declaration) of the uniforms, blocks are listed with their members
+    fn uniforms(&self, module: &Module) -> Vec<(String, String)> {
+        let mut uniforms = Vec::new();
+        for (_, variable) in module.global_variables.iter() {
+            // blocks without an instance name go by their block name
+            let Some(name) = variable.name.as_ref().or(module.types[variable.ty].name.as_ref()) else { continue };
+            match variable.space {
+                AddressSpace::Handle => {
+                    if let Some(texture) = name.strip_suffix("_texture") {
+                        let type_name = self.samplers.get(texture).cloned().unwrap_or_else(|| type_name(module, variable.ty));
+                        uniforms.push((texture.to_string(), format!("{} {}", type_name, texture)));
+                    }
+                }
+                AddressSpace::Uniform if variable.name.is_none() => uniforms.push((name.clone(), type_name(module, variable.ty))),
+                AddressSpace::Uniform => uniforms.push((name.clone(), format!("{} {}", type_name(module, variable.ty), name))),
+                _ => {}
+            }
+        }
+        uniforms
+    }
+}
+
+// adds a binding to a `uniform` declaration or block and splits combined samplers in two
+fn rewrite_uniform(line: &str, binding: &mut u32, samplers: &mut HashMap<String, String>) -> Option<String> {
+    let (layout, rest) = split_layout(line.trim_start());
+    let rest = rest.strip_prefix("uniform")?;
+    if !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let rest = rest.trim_start();
+    let uniform_type = rest.split(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).next()?;
+
+    if let Some((texture_type, sampler_type)) = split_sampler_type(uniform_type) {
+        // a single sampler, arrays can't be put back together by a #define
+        let name = rest[uniform_type.len()..].trim().strip_suffix(';')?.trim();
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return None;
+        }
+        samplers.insert(name.to_string(), uniform_type.to_string());
+        *binding += 2;
+        return Some(format!(
+            "layout(binding = {}) uniform {} {}_texture; layout(binding = {}) uniform {} {}_sampler;",
+            *binding - 2, texture_type, name, *binding - 1, sampler_type, name
+        ));
+    }
+
+    let layout = match layout {
+        Some(layout) if layout.contains("binding") => return None,
+        Some(layout) => format!("{}, binding = {}", layout, binding),
+        None => format!("binding = {}", binding)
+    };
+    *binding += 1;
+    Some(format!("layout({}) uniform {}", layout, rest))
+}
+
+// "layout(std140) uniform ..." -> (Some("std140"), "uniform ...")
+fn split_layout(line: &str) -> (Option<&str>, &str) {
+    let layout = line
+        .strip_prefix("layout")
+        .map(str::trim_start)
+        .and_then(|rest| rest.strip_prefix('('))
+        .and_then(|rest| rest.split_once(')'));
+    match layout {
+        Some((layout, rest)) => (Some(layout.trim()), rest.trim_start()),
+        None => (None, line)
+    }
+}
+
+// "usampler2DArray" -> ("utexture2DArray", "sampler"), "sampler2DShadow" -> ("texture2D", "samplerShadow")
+fn split_sampler_type(uniform_type: &str) -> Option<(String, &'static str)> {
+    let prefix = if uniform_type.starts_with("isampler") || uniform_type.starts_with("usampler") { &uniform_type[..1] } else { "" };
+    let dimension = uniform_type[prefix.len()..].strip_prefix("sampler")?;
+    let (dimension, sampler_type) = match dimension.strip_suffix("Shadow") {
+        Some(dimension) => (dimension, "samplerShadow"),
+        None => (dimension, "sampler")
+    };
+    // a plain `sampler` is already separate
+    if dimension.is_empty() {
+        return None;
+    }
+    Some((format!("{}texture{}", prefix, dimension), sampler_type))
+}
+
+fn type_name(module: &Module, ty: naga::Handle<naga::Type>) -> String {
+    let scalar_prefix = |kind| match kind {
+        ScalarKind::Sint | ScalarKind::AbstractInt => "i",
+        ScalarKind::Uint => "u",
+        ScalarKind::Bool => "b",
+        ScalarKind::Float | ScalarKind::AbstractFloat => ""
+    };
+    match &module.types[ty].inner {
+        TypeInner::Scalar(scalar) => match scalar.kind {
+            ScalarKind::Sint | ScalarKind::AbstractInt => "int",
+            ScalarKind::Uint => "uint",
+            ScalarKind::Bool => "bool",
+            ScalarKind::Float | ScalarKind::AbstractFloat => "float"
+        }.to_string(),
+        TypeInner::Vector { size, scalar } => format!("{}vec{}", scalar_prefix(scalar.kind), *size as u8),
+        TypeInner::Matrix { columns, rows, .. } if columns == rows => format!("mat{}", *columns as u8),
+        TypeInner::Matrix { columns, rows, .. } => format!("mat{}x{}", *columns as u8, *rows as u8),
+        TypeInner::Array { base, size: ArraySize::Constant(size), .. } => format!("{}[{}]", type_name(module, *base), size),
+        TypeInner::Array { base, .. } => format!("{}[]", type_name(module, *base)),
+        TypeInner::Struct { members, .. } => {
+            let members: Vec<String> = members
+                .iter()
+                .map(|member| format!("{} {};", type_name(module, member.ty), member.name.as_deref().unwrap_or("_")))
+                .collect();
+            format!("{} {{ {} }}", module.types[ty].name.as_deref().unwrap_or("block"), members.join(" "))
+        }
+        _ => "unknown type".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use egui_glfw_gl2::shader::ShaderStage;
+
+    use super::{rewrite_uniform, VulkanGlsl};
+
+    #[test]
+    fn rewrites_opengl_uniforms() {
+        let mut binding = 0;
+        let mut samplers = HashMap::new();
+        let mut rewrite = |line| rewrite_uniform(line, &mut binding, &mut samplers);
+        assert_eq!(rewrite("uniform vec2 uScreenSize;").as_deref(), Some("layout(binding = 0) uniform vec2 uScreenSize;"));
+        assert_eq!(
+            rewrite("uniform sampler2DShadow uShadow;").as_deref(),
+            Some("layout(binding = 1) uniform texture2D uShadow_texture; layout(binding = 2) uniform samplerShadow uShadow_sampler;")
+        );
+        assert_eq!(rewrite("  layout (std140) uniform Camera {").as_deref(), Some("layout(std140, binding = 3) uniform Camera {"));
+        assert_eq!(rewrite("layout(binding = 7) uniform Lights {"), None);
+        assert_eq!(rewrite("uniform sampler2D uTextures[4];"), None);
+        assert_eq!(rewrite("uniformly lit"), None);
+        assert_eq!(samplers.get("uShadow").map(String::as_str), Some("sampler2DShadow"));
+    }
+
+    #[test]
+    fn keeps_lines_in_place() {
+        let source = "#line 1 0\n#version 330 core\nuniform sampler2D uSampler;\nvoid main() {\n    gl_FragColor = texture2D(uSampler, vec2(0.0));\n}";
+        let vulkan = VulkanGlsl::new(source, ShaderStage::Fragment);
+        let lines: Vec<&str> = vulkan.source.lines().collect();
+        assert_eq!(lines[1], "#version 450 core");
+        assert_eq!(lines.last(), Some(&"}"));
+        assert_eq!(lines.len(), source.lines().count() + vulkan.header_len);
+        assert_eq!(vulkan.source_line(2), Some(2));
+        assert_eq!(vulkan.source_line(3), None);
+        assert_eq!(vulkan.source_line(lines.len()), Some(6));
+    }
+}
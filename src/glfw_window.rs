@@ -58,43 +58,14 @@ impl GlfwWindow {
     }
 
     fn init(&self) -> (Glfw, PWindow, GlfwReceiver<(f64, WindowEvent)>) {
-        unsafe {
-            // Initialize GLFW
-            let mut glfw = glfw::init_no_callbacks().expect("Error: Unable to initialize GLFW.");
-            glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
-            glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
-            glfw.window_hint(glfw::WindowHint::SRgbCapable(true));
-            glfw.window_hint(glfw::WindowHint::DoubleBuffer(true));
-            glfw.window_hint(glfw::WindowHint::TransparentFramebuffer(false));
-            glfw.window_hint(glfw::WindowHint::RedBits(Some(8)));
-            glfw.window_hint(glfw::WindowHint::GreenBits(Some(8)));
-            glfw.window_hint(glfw::WindowHint::BlueBits(Some(8)));
-            glfw.window_hint(glfw::WindowHint::AlphaBits(Some(8)));
-            glfw.window_hint(glfw::WindowHint::DepthBits(Some(24)));
-            glfw.window_hint(glfw::WindowHint::StencilBits(Some(8)));
-            glfw.window_hint(glfw::WindowHint::Samples(Some(4)));
-            glfw.window_hint(glfw::WindowHint::Resizable(true));
-            // Create Window
-            let (mut window, events) = glfw
-                .create_window(self.width, self.height, self.title.as_str(), glfw::WindowMode::Windowed)
-                .expect("Error: Failed to create GLFW window.");
-            // Enable window event input
-            window.set_all_polling(true);
-            // Make the OpenGL context current
-            window.make_current();
-            // Enable v-sync
-            glfw.set_swap_interval(glfw::SwapInterval::Sync(1));
-            // Init OpenGL
-            init_gl(&mut window);
-            // settings
-            glEnable(GL_FRAMEBUFFER_SRGB);
-            glEnable(GL_MULTISAMPLE);
-            glEnable(GL_BLEND);
-            glBlendFunc(GL_ONE, GL_ONE_MINUS_SRC_ALPHA);
-            // Make the window visible
-            window.show();
-            (glfw, window, events)
-        }
+        let (mut glfw, mut window, events) = create_gl_window(self.width, self.height, self.title.as_str(), glfw::ContextCreationApi::Native);
+        // Enable window event input
+        window.set_all_polling(true);
+        // Enable v-sync
+        glfw.set_swap_interval(glfw::SwapInterval::Sync(1));
+        // Make the window visible
+        window.show();
+        (glfw, window, events)
     }
 
     unsafe fn event_loop(&mut self, mut glfw: Glfw, mut window: PWindow, events: GlfwReceiver<(f64, WindowEvent)>) {
@@ -151,6 +122,44 @@ impl GlfwWindow {
     }
 }
 
+// Creates a hidden window with a current OpenGL 3.3 core context and the gl state egui expects.
+// glfwInit needs an X11 or Wayland display whatever the context creation api is.
+pub(crate) fn create_gl_window(width: u32, height: u32, title: &str, context_api: glfw::ContextCreationApi) -> (Glfw, PWindow, GlfwReceiver<(f64, WindowEvent)>) {
+    unsafe {
+        // Initialize GLFW
+        let mut glfw = glfw::init_no_callbacks().expect("Error: Unable to initialize GLFW.");
+        glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
+        glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
+        glfw.window_hint(glfw::WindowHint::ContextCreationApi(context_api));
+        glfw.window_hint(glfw::WindowHint::SRgbCapable(true));
+        glfw.window_hint(glfw::WindowHint::DoubleBuffer(true));
+        glfw.window_hint(glfw::WindowHint::TransparentFramebuffer(false));
+        glfw.window_hint(glfw::WindowHint::RedBits(Some(8)));
+        glfw.window_hint(glfw::WindowHint::GreenBits(Some(8)));
+        glfw.window_hint(glfw::WindowHint::BlueBits(Some(8)));
+        glfw.window_hint(glfw::WindowHint::AlphaBits(Some(8)));
+        glfw.window_hint(glfw::WindowHint::DepthBits(Some(24)));
+        glfw.window_hint(glfw::WindowHint::StencilBits(Some(8)));
+        glfw.window_hint(glfw::WindowHint::Samples(Some(4)));
+        glfw.window_hint(glfw::WindowHint::Resizable(true));
+        glfw.window_hint(glfw::WindowHint::Visible(false));
+        // Create Window
+        let (mut window, events) = glfw
+            .create_window(width, height, title, glfw::WindowMode::Windowed)
+            .expect("Error: Failed to create GLFW window.");
+        // Make the OpenGL context current
+        window.make_current();
+        // Init OpenGL
+        init_gl(&mut window);
+        // settings
+        glEnable(GL_FRAMEBUFFER_SRGB);
+        glEnable(GL_MULTISAMPLE);
+        glEnable(GL_BLEND);
+        glBlendFunc(GL_ONE, GL_ONE_MINUS_SRC_ALPHA);
+        (glfw, window, events)
+    }
+}
+
 fn init_gl(window: &mut PWindow) {
     let window = Mutex::new(window);
     unsafe {
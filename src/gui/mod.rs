@@ -2,12 +2,23 @@ pub use self::raw_input_translate::*;
 pub use self::ui_input::*;
 pub use self::ui_render::*;
 pub use self::ui_context::*;
+pub use self::ui_callback::*;
+pub use self::ui_gl_state::*;
+pub use self::ui_texture::UserTexture;
+pub use self::ui_render_target::*;
+pub use self::ui_shortcuts::*;
+pub use self::ui_scroll::*;
 
 mod raw_input_translate;
 mod ui_input;
 mod ui_render;
 mod ui_texture;
 mod ui_context;
+mod ui_callback;
+mod ui_gl_state;
+mod ui_render_target;
+mod ui_shortcuts;
+mod ui_scroll;
 
 pub trait UiComponent {
     fn init(&mut self, gui_ctx: &mut GuiContext);
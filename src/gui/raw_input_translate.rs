@@ -1,41 +1,10 @@
-use egui::{CursorIcon, Key, Modifiers};
-use glfw::Modifiers as Mod;
+use egui::{CursorIcon, Key, PointerButton};
+use glfw::StandardCursor;
+#[cfg(target_os = "windows")]
 use winapi::ctypes::wchar_t;
+#[cfg(target_os = "windows")]
 use winapi::um::winuser;
 
-pub fn translate_modifiers(keymod: Mod) -> Modifiers {
-    Modifiers {
-        alt: keymod & Mod::Alt == Mod::Alt,
-        ctrl: keymod & Mod::Control == Mod::Control,
-        shift: keymod & Mod::Shift == Mod::Shift,
-        command: keymod & Mod::Control == Mod::Control,
-        // TODO: GLFW doesn't seem to support the mac command key
-        //       mac_cmd: keymod & Mod::LGUIMOD == Mod::LGUIMOD,
-        mac_cmd: false,
-    }
-}
-
-pub fn is_cut_command(modifiers: Modifiers, keycode: glfw::Key) -> bool {
-    (modifiers.command && keycode == glfw::Key::X)
-        || (cfg!(target_os = "windows")
-        && modifiers.shift
-        && keycode == glfw::Key::Delete)
-}
-
-pub fn is_copy_command(modifiers: Modifiers, keycode: glfw::Key) -> bool {
-    (modifiers.command && keycode == glfw::Key::C)
-        || (cfg!(target_os = "windows")
-        && modifiers.ctrl
-        && keycode == glfw::Key::Insert)
-}
-
-pub fn is_paste_command(modifiers: Modifiers, keycode: glfw::Key) -> bool {
-    (modifiers.command && keycode == glfw::Key::V)
-        || (cfg!(target_os = "windows")
-        && modifiers.shift
-        && keycode == glfw::Key::Insert)
-}
-
 pub fn translate_virtual_key_code(key: glfw::Key) -> Option<Key> {
     use glfw::Key::*;
 
@@ -59,6 +28,28 @@ pub fn translate_virtual_key_code(key: glfw::Key) -> Option<Key> {
         PageDown => Key::PageDown,
         PageUp => Key::PageUp,
 
+        Comma => Key::Comma,
+        Minus => Key::Minus,
+        Period => Key::Period,
+        Slash => Key::Slash,
+        Semicolon => Key::Semicolon,
+        Equal => Key::Equals,
+        LeftBracket => Key::OpenBracket,
+        Backslash => Key::Backslash,
+        RightBracket => Key::CloseBracket,
+        GraveAccent => Key::Backtick,
+
+        Num0 => Key::Num0,
+        Num1 => Key::Num1,
+        Num2 => Key::Num2,
+        Num3 => Key::Num3,
+        Num4 => Key::Num4,
+        Num5 => Key::Num5,
+        Num6 => Key::Num6,
+        Num7 => Key::Num7,
+        Num8 => Key::Num8,
+        Num9 => Key::Num9,
+
         A => Key::A,
         B => Key::B,
         C => Key::C,
@@ -86,14 +77,239 @@ pub fn translate_virtual_key_code(key: glfw::Key) -> Option<Key> {
         Y => Key::Y,
         Z => Key::Z,
 
+        F1 => Key::F1,
+        F2 => Key::F2,
+        F3 => Key::F3,
+        F4 => Key::F4,
+        F5 => Key::F5,
+        F6 => Key::F6,
+        F7 => Key::F7,
+        F8 => Key::F8,
+        F9 => Key::F9,
+        F10 => Key::F10,
+        F11 => Key::F11,
+        F12 => Key::F12,
+        F13 => Key::F13,
+        F14 => Key::F14,
+        F15 => Key::F15,
+        F16 => Key::F16,
+        F17 => Key::F17,
+        F18 => Key::F18,
+        F19 => Key::F19,
+        F20 => Key::F20,
+        F21 => Key::F21,
+        F22 => Key::F22,
+        F23 => Key::F23,
+        F24 => Key::F24,
+        F25 => Key::F25,
+
+        // egui has no keypad keys, they act like the keys they type
+        Kp0 => Key::Num0,
+        Kp1 => Key::Num1,
+        Kp2 => Key::Num2,
+        Kp3 => Key::Num3,
+        Kp4 => Key::Num4,
+        Kp5 => Key::Num5,
+        Kp6 => Key::Num6,
+        Kp7 => Key::Num7,
+        Kp8 => Key::Num8,
+        Kp9 => Key::Num9,
+        KpDecimal => Key::Period,
+        KpDivide => Key::Slash,
+        KpSubtract => Key::Minus,
+        KpAdd => Key::Plus,
+        KpEnter => Key::Enter,
+        KpEqual => Key::Equals,
+
         _ => {
             return None;
         }
     })
 }
 
+/// The key the keyboard layout puts at the position of `key`, `key_name` as returned by `glfw::get_key_name`.
+///
+/// GLFW key codes name positions on a US keyboard, which is what egui calls the physical key. Keys GLFW
+/// has no printable name for, or names egui doesn't know, keep the physical key.
+pub fn translate_logical_key(key: glfw::Key, key_name: Option<&str>) -> Option<Key> {
+    let from_name = key_name
+        .filter(|name| name.chars().count() == 1)
+        .and_then(|name| Key::from_name(&name.to_uppercase()));
+    from_name.or_else(|| translate_virtual_key_code(key))
+}
+
+/// The inverse of `translate_virtual_key_code`, keys that are also on the keypad map to the main keyboard.
+pub fn translate_egui_key(key: Key) -> Option<glfw::Key> {
+    use glfw::Key::*;
+
+    Some(match key {
+        Key::ArrowLeft => Left,
+        Key::ArrowUp => Up,
+        Key::ArrowRight => Right,
+        Key::ArrowDown => Down,
 
-pub fn translate_cursor(cursor_icon: CursorIcon) -> Option<WinCursorIcon> {
+        Key::Escape => Escape,
+        Key::Tab => Tab,
+        Key::Backspace => Backspace,
+        Key::Space => Space,
+
+        Key::Enter => Enter,
+
+        Key::Insert => Insert,
+        Key::Home => Home,
+        Key::Delete => Delete,
+        Key::End => End,
+        Key::PageDown => PageDown,
+        Key::PageUp => PageUp,
+
+        Key::Comma => Comma,
+        Key::Minus => Minus,
+        Key::Period => Period,
+        Key::Slash => Slash,
+        Key::Semicolon => Semicolon,
+        Key::Equals => Equal,
+        Key::OpenBracket => LeftBracket,
+        Key::Backslash => Backslash,
+        Key::CloseBracket => RightBracket,
+        Key::Backtick => GraveAccent,
+        // only the keypad has a key of its own for +
+        Key::Plus => KpAdd,
+
+        Key::Num0 => Num0,
+        Key::Num1 => Num1,
+        Key::Num2 => Num2,
+        Key::Num3 => Num3,
+        Key::Num4 => Num4,
+        Key::Num5 => Num5,
+        Key::Num6 => Num6,
+        Key::Num7 => Num7,
+        Key::Num8 => Num8,
+        Key::Num9 => Num9,
+
+        Key::A => A,
+        Key::B => B,
+        Key::C => C,
+        Key::D => D,
+        Key::E => E,
+        Key::F => F,
+        Key::G => G,
+        Key::H => H,
+        Key::I => I,
+        Key::J => J,
+        Key::K => K,
+        Key::L => L,
+        Key::M => M,
+        Key::N => N,
+        Key::O => O,
+        Key::P => P,
+        Key::Q => Q,
+        Key::R => R,
+        Key::S => S,
+        Key::T => T,
+        Key::U => U,
+        Key::V => V,
+        Key::W => W,
+        Key::X => X,
+        Key::Y => Y,
+        Key::Z => Z,
+
+        Key::F1 => F1,
+        Key::F2 => F2,
+        Key::F3 => F3,
+        Key::F4 => F4,
+        Key::F5 => F5,
+        Key::F6 => F6,
+        Key::F7 => F7,
+        Key::F8 => F8,
+        Key::F9 => F9,
+        Key::F10 => F10,
+        Key::F11 => F11,
+        Key::F12 => F12,
+        Key::F13 => F13,
+        Key::F14 => F14,
+        Key::F15 => F15,
+        Key::F16 => F16,
+        Key::F17 => F17,
+        Key::F18 => F18,
+        Key::F19 => F19,
+        Key::F20 => F20,
+        Key::F21 => F21,
+        Key::F22 => F22,
+        Key::F23 => F23,
+        Key::F24 => F24,
+        Key::F25 => F25,
+
+        // Copy, Cut, Paste, symbols typed with Shift and F26 to F35
+        _ => {
+            return None;
+        }
+    })
+}
+
+/// Buttons 4 and 5 are usually back and forward, egui has no buttons beyond them.
+pub fn translate_mouse_button(button: glfw::MouseButton) -> Option<PointerButton> {
+    Some(match button {
+        glfw::MouseButtonLeft => PointerButton::Primary,
+        glfw::MouseButtonRight => PointerButton::Secondary,
+        glfw::MouseButtonMiddle => PointerButton::Middle,
+        glfw::MouseButton::Button4 => PointerButton::Extra1,
+        glfw::MouseButton::Button5 => PointerButton::Extra2,
+        _ => {
+            return None;
+        }
+    })
+}
+
+/// GLFW 3.3 only ships six standard cursor shapes, the remaining egui icons fall back to the closest one.
+pub fn translate_cursor(cursor_icon: CursorIcon) -> Option<StandardCursor> {
+    match cursor_icon {
+        CursorIcon::None => None,
+
+        CursorIcon::Crosshair
+        | CursorIcon::Cell
+        | CursorIcon::Move
+        | CursorIcon::AllScroll => Some(StandardCursor::Crosshair),
+
+        CursorIcon::PointingHand
+        | CursorIcon::Grab
+        | CursorIcon::Grabbing => Some(StandardCursor::Hand),
+
+        CursorIcon::Text | CursorIcon::VerticalText => Some(StandardCursor::IBeam),
+
+        CursorIcon::ResizeHorizontal
+        | CursorIcon::ResizeEast
+        | CursorIcon::ResizeWest
+        | CursorIcon::ResizeColumn => Some(StandardCursor::HResize),
+
+        CursorIcon::ResizeVertical
+        | CursorIcon::ResizeNorth
+        | CursorIcon::ResizeSouth
+        | CursorIcon::ResizeRow => Some(StandardCursor::VResize),
+
+        // no diagonal resize shapes before GLFW 3.4
+        CursorIcon::ResizeNeSw
+        | CursorIcon::ResizeNwSe
+        | CursorIcon::ResizeNorthEast
+        | CursorIcon::ResizeNorthWest
+        | CursorIcon::ResizeSouthEast
+        | CursorIcon::ResizeSouthWest => Some(StandardCursor::Crosshair),
+
+        CursorIcon::Default
+        | CursorIcon::Alias
+        | CursorIcon::ContextMenu
+        | CursorIcon::Copy
+        | CursorIcon::Help
+        | CursorIcon::NoDrop
+        | CursorIcon::NotAllowed
+        | CursorIcon::Progress
+        | CursorIcon::Wait
+        | CursorIcon::ZoomIn
+        | CursorIcon::ZoomOut => Some(StandardCursor::Arrow),
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub fn translate_win_cursor(cursor_icon: CursorIcon) -> Option<WinCursorIcon> {
     match cursor_icon {
         CursorIcon::None => None,
 
@@ -137,6 +353,7 @@ pub fn translate_cursor(cursor_icon: CursorIcon) -> Option<WinCursorIcon> {
     }
 }
 
+#[cfg(target_os = "windows")]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum WinCursorIcon {
@@ -195,12 +412,14 @@ pub enum WinCursorIcon {
     RowResize,
 }
 
+#[cfg(target_os = "windows")]
 impl Default for WinCursorIcon {
     fn default() -> Self {
         WinCursorIcon::Default
     }
 }
 
+#[cfg(target_os = "windows")]
 impl WinCursorIcon {
     pub(crate) fn to_windows_cursor(self) -> *const wchar_t {
         match self {
@@ -233,3 +452,187 @@ impl WinCursorIcon {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use egui::{Key, PointerButton};
+    use glfw::{Key as GlfwKey, MouseButton};
+
+    use super::{translate_egui_key, translate_logical_key, translate_mouse_button, translate_virtual_key_code};
+
+    // (GLFW key, egui key, true if egui maps back to this GLFW key)
+    const KEYS: &[(GlfwKey, Key, bool)] = &[
+        (GlfwKey::Left, Key::ArrowLeft, true),
+        (GlfwKey::Up, Key::ArrowUp, true),
+        (GlfwKey::Right, Key::ArrowRight, true),
+        (GlfwKey::Down, Key::ArrowDown, true),
+        (GlfwKey::Escape, Key::Escape, true),
+        (GlfwKey::Tab, Key::Tab, true),
+        (GlfwKey::Backspace, Key::Backspace, true),
+        (GlfwKey::Space, Key::Space, true),
+        (GlfwKey::Enter, Key::Enter, true),
+        (GlfwKey::Insert, Key::Insert, true),
+        (GlfwKey::Home, Key::Home, true),
+        (GlfwKey::Delete, Key::Delete, true),
+        (GlfwKey::End, Key::End, true),
+        (GlfwKey::PageDown, Key::PageDown, true),
+        (GlfwKey::PageUp, Key::PageUp, true),
+        (GlfwKey::Comma, Key::Comma, true),
+        (GlfwKey::Minus, Key::Minus, true),
+        (GlfwKey::Period, Key::Period, true),
+        (GlfwKey::Slash, Key::Slash, true),
+        (GlfwKey::Semicolon, Key::Semicolon, true),
+        (GlfwKey::Equal, Key::Equals, true),
+        (GlfwKey::LeftBracket, Key::OpenBracket, true),
+        (GlfwKey::Backslash, Key::Backslash, true),
+        (GlfwKey::RightBracket, Key::CloseBracket, true),
+        (GlfwKey::GraveAccent, Key::Backtick, true),
+        (GlfwKey::Num0, Key::Num0, true),
+        (GlfwKey::Num1, Key::Num1, true),
+        (GlfwKey::Num2, Key::Num2, true),
+        (GlfwKey::Num3, Key::Num3, true),
+        (GlfwKey::Num4, Key::Num4, true),
+        (GlfwKey::Num5, Key::Num5, true),
+        (GlfwKey::Num6, Key::Num6, true),
+        (GlfwKey::Num7, Key::Num7, true),
+        (GlfwKey::Num8, Key::Num8, true),
+        (GlfwKey::Num9, Key::Num9, true),
+        (GlfwKey::A, Key::A, true),
+        (GlfwKey::B, Key::B, true),
+        (GlfwKey::C, Key::C, true),
+        (GlfwKey::D, Key::D, true),
+        (GlfwKey::E, Key::E, true),
+        (GlfwKey::F, Key::F, true),
+        (GlfwKey::G, Key::G, true),
+        (GlfwKey::H, Key::H, true),
+        (GlfwKey::I, Key::I, true),
+        (GlfwKey::J, Key::J, true),
+        (GlfwKey::K, Key::K, true),
+        (GlfwKey::L, Key::L, true),
+        (GlfwKey::M, Key::M, true),
+        (GlfwKey::N, Key::N, true),
+        (GlfwKey::O, Key::O, true),
+        (GlfwKey::P, Key::P, true),
+        (GlfwKey::Q, Key::Q, true),
+        (GlfwKey::R, Key::R, true),
+        (GlfwKey::S, Key::S, true),
+        (GlfwKey::T, Key::T, true),
+        (GlfwKey::U, Key::U, true),
+        (GlfwKey::V, Key::V, true),
+        (GlfwKey::W, Key::W, true),
+        (GlfwKey::X, Key::X, true),
+        (GlfwKey::Y, Key::Y, true),
+        (GlfwKey::Z, Key::Z, true),
+        (GlfwKey::F1, Key::F1, true),
+        (GlfwKey::F2, Key::F2, true),
+        (GlfwKey::F3, Key::F3, true),
+        (GlfwKey::F4, Key::F4, true),
+        (GlfwKey::F5, Key::F5, true),
+        (GlfwKey::F6, Key::F6, true),
+        (GlfwKey::F7, Key::F7, true),
+        (GlfwKey::F8, Key::F8, true),
+        (GlfwKey::F9, Key::F9, true),
+        (GlfwKey::F10, Key::F10, true),
+        (GlfwKey::F11, Key::F11, true),
+        (GlfwKey::F12, Key::F12, true),
+        (GlfwKey::F13, Key::F13, true),
+        (GlfwKey::F14, Key::F14, true),
+        (GlfwKey::F15, Key::F15, true),
+        (GlfwKey::F16, Key::F16, true),
+        (GlfwKey::F17, Key::F17, true),
+        (GlfwKey::F18, Key::F18, true),
+        (GlfwKey::F19, Key::F19, true),
+        (GlfwKey::F20, Key::F20, true),
+        (GlfwKey::F21, Key::F21, true),
+        (GlfwKey::F22, Key::F22, true),
+        (GlfwKey::F23, Key::F23, true),
+        (GlfwKey::F24, Key::F24, true),
+        (GlfwKey::F25, Key::F25, true),
+        (GlfwKey::Kp0, Key::Num0, false),
+        (GlfwKey::Kp1, Key::Num1, false),
+        (GlfwKey::Kp2, Key::Num2, false),
+        (GlfwKey::Kp3, Key::Num3, false),
+        (GlfwKey::Kp4, Key::Num4, false),
+        (GlfwKey::Kp5, Key::Num5, false),
+        (GlfwKey::Kp6, Key::Num6, false),
+        (GlfwKey::Kp7, Key::Num7, false),
+        (GlfwKey::Kp8, Key::Num8, false),
+        (GlfwKey::Kp9, Key::Num9, false),
+        (GlfwKey::KpDecimal, Key::Period, false),
+        (GlfwKey::KpDivide, Key::Slash, false),
+        (GlfwKey::KpSubtract, Key::Minus, false),
+        (GlfwKey::KpAdd, Key::Plus, true),
+        (GlfwKey::KpEnter, Key::Enter, false),
+        (GlfwKey::KpEqual, Key::Equals, false),
+    ];
+
+    const UNMAPPED_GLFW_KEYS: &[GlfwKey] = &[
+        GlfwKey::Apostrophe, GlfwKey::World1, GlfwKey::World2, GlfwKey::CapsLock, GlfwKey::ScrollLock, GlfwKey::NumLock,
+        GlfwKey::PrintScreen, GlfwKey::Pause, GlfwKey::KpMultiply, GlfwKey::LeftShift, GlfwKey::LeftControl, GlfwKey::LeftAlt,
+        GlfwKey::LeftSuper, GlfwKey::RightShift, GlfwKey::RightControl, GlfwKey::RightAlt, GlfwKey::RightSuper, GlfwKey::Menu,
+        GlfwKey::Unknown
+    ];
+
+    #[test]
+    fn translates_glfw_keys_to_egui() {
+        for &(glfw_key, key, _) in KEYS {
+            assert_eq!(translate_virtual_key_code(glfw_key), Some(key), "{:?}", glfw_key);
+        }
+        for &glfw_key in UNMAPPED_GLFW_KEYS {
+            assert_eq!(translate_virtual_key_code(glfw_key), None, "{:?}", glfw_key);
+        }
+    }
+
+    #[test]
+    fn translates_egui_keys_to_glfw() {
+        for &(glfw_key, key, _) in KEYS.iter().filter(|(_, _, inverse)| *inverse) {
+            assert_eq!(translate_egui_key(key), Some(glfw_key), "{:?}", key);
+        }
+        // every egui key either round-trips or is in no table entry at all
+        for &key in Key::ALL {
+            match translate_egui_key(key) {
+                Some(glfw_key) => assert_eq!(translate_virtual_key_code(glfw_key), Some(key), "{:?}", key),
+                None => assert!(KEYS.iter().all(|(_, mapped, _)| *mapped != key), "{:?} has a GLFW key", key)
+            }
+        }
+    }
+
+    #[test]
+    fn translates_logical_keys_from_the_layout() {
+        let cases = [
+            // AZERTY
+            (GlfwKey::Q, Some("a"), Key::A),
+            (GlfwKey::Semicolon, Some("m"), Key::M),
+            // "&" isn't an egui key, the digit row keeps its physical key
+            (GlfwKey::Num1, Some("&"), Key::Num1),
+            // QWERTZ
+            (GlfwKey::Z, Some("y"), Key::Y),
+            // Dvorak
+            (GlfwKey::LeftBracket, Some("/"), Key::Slash),
+            (GlfwKey::Kp5, Some("5"), Key::Num5),
+            (GlfwKey::Left, None, Key::ArrowLeft),
+            (GlfwKey::A, Some("ä"), Key::A)
+        ];
+        for (glfw_key, key_name, key) in cases {
+            assert_eq!(translate_logical_key(glfw_key, key_name), Some(key), "{:?} {:?}", glfw_key, key_name);
+        }
+        assert_eq!(translate_logical_key(GlfwKey::Unknown, None), None);
+    }
+
+    #[test]
+    fn translates_mouse_buttons() {
+        let cases = [
+            (MouseButton::Button1, Some(PointerButton::Primary)),
+            (MouseButton::Button2, Some(PointerButton::Secondary)),
+            (MouseButton::Button3, Some(PointerButton::Middle)),
+            (MouseButton::Button4, Some(PointerButton::Extra1)),
+            (MouseButton::Button5, Some(PointerButton::Extra2)),
+            (MouseButton::Button6, None),
+            (MouseButton::Button7, None),
+            (MouseButton::Button8, None)
+        ];
+        for (glfw_button, button) in cases {
+            assert_eq!(translate_mouse_button(glfw_button), button, "{:?}", glfw_button);
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use egui::PaintCallbackInfo;
+
+/// Custom paint callback for this backend, put it into `egui::PaintCallback::callback`.
+///
+/// The viewport is already set to the callback rect and the scissor to the clip rect when it is called,
+/// all gl state touched inside is restored by `GuiRender` afterwards.
+pub struct CallbackFn {
+    f: Box<dyn Fn(PaintCallbackInfo) + Sync + Send>,
+}
+
+impl CallbackFn {
+    pub fn new<F: Fn(PaintCallbackInfo) + Sync + Send + 'static>(callback: F) -> Self {
+        Self {
+            f: Box::new(callback),
+        }
+    }
+
+    pub fn call(&self, info: PaintCallbackInfo) {
+        (self.f)(info)
+    }
+}
@@ -12,8 +12,12 @@ pub struct GuiContext {
 impl GuiContext {
     pub fn new(window: &mut PWindow) -> Self {
         let pixels_per_point = window.get_content_scale().0;
-
         let (width, height) = window.get_framebuffer_size();
+        Self::from_size(width as usize, height as usize, pixels_per_point)
+    }
+
+    /// Does not touch any window, only needs a current OpenGL context (e.g. for offscreen rendering).
+    pub fn from_size(width: usize, height: usize, pixels_per_point: f32) -> Self {
         let raw_input = RawInput {
             screen_rect: Some(Rect::from_min_size(Pos2::new(0f32, 0f32), vec2(width as f32, height as f32) / pixels_per_point)),
             ..Default::default()
@@ -23,7 +27,7 @@ impl GuiContext {
         egui_ctx.set_pixels_per_point(pixels_per_point);
 
         GuiContext {
-            gui_render: GuiRender::new(width as usize, height as usize),
+            gui_render: GuiRender::new(width, height),
             egui_ctx,
             user_input: GuiInput::new(raw_input)
         }
@@ -43,7 +47,7 @@ impl GuiContext {
         // handle egui events
         let egui_output = self.egui_ctx.end_frame();
         let platform_output = &egui_output.platform_output;
-        self.user_input.handle_platform_output(window, &platform_output);
+        self.user_input.handle_platform_output(window, platform_output);
         self.user_input.handle_event(window, events, pixels_per_point);
         egui_output
     }
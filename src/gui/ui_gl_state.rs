@@ -0,0 +1,169 @@
+use gl33::*;
+use gl33::global_loader::*;
+
+/// Snapshot of the gl state `GuiRender` touches, used to hand the pipeline back to the caller unchanged.
+///
+/// Not preserved: bindings of texture units other than 0, uniform buffer bindings, the pixel pack state and
+/// anything else a paint callback changes beyond the state listed here.
+pub struct GlState {
+    viewport: [i32; 4],
+    scissor_box: [i32; 4],
+    polygon_mode: [i32; 2],
+    color_mask: [u8; 4],
+    depth_mask: u8,
+
+    scissor_test: bool,
+    blend: bool,
+    depth_test: bool,
+    cull_face: bool,
+    stencil_test: bool,
+    framebuffer_srgb: bool,
+
+    blend_src_rgb: i32,
+    blend_dst_rgb: i32,
+    blend_src_alpha: i32,
+    blend_dst_alpha: i32,
+    blend_equation_rgb: i32,
+    blend_equation_alpha: i32,
+
+    draw_framebuffer: i32,
+    read_framebuffer: i32,
+    unpack_alignment: i32,
+    unpack_row_length: i32,
+
+    program: i32,
+    vertex_array: i32,
+    array_buffer: i32,
+    active_texture: i32,
+    // bindings of texture unit 0, the unit egui samples from
+    texture_2d: i32,
+    sampler: i32,
+}
+
+impl GlState {
+    pub fn capture() -> Self {
+        unsafe {
+            let mut state = GlState {
+                viewport: [0; 4],
+                scissor_box: [0; 4],
+                polygon_mode: [0; 2],
+                color_mask: [0; 4],
+                depth_mask: 0,
+
+                scissor_test: glIsEnabled(GL_SCISSOR_TEST) != 0,
+                blend: glIsEnabled(GL_BLEND) != 0,
+                depth_test: glIsEnabled(GL_DEPTH_TEST) != 0,
+                cull_face: glIsEnabled(GL_CULL_FACE) != 0,
+                stencil_test: glIsEnabled(GL_STENCIL_TEST) != 0,
+                framebuffer_srgb: glIsEnabled(GL_FRAMEBUFFER_SRGB) != 0,
+
+                blend_src_rgb: get_integer(GL_BLEND_SRC_RGB),
+                blend_dst_rgb: get_integer(GL_BLEND_DST_RGB),
+                blend_src_alpha: get_integer(GL_BLEND_SRC_ALPHA),
+                blend_dst_alpha: get_integer(GL_BLEND_DST_ALPHA),
+                blend_equation_rgb: get_integer(GL_BLEND_EQUATION_RGB),
+                blend_equation_alpha: get_integer(GL_BLEND_EQUATION_ALPHA),
+
+                draw_framebuffer: get_integer(GL_DRAW_FRAMEBUFFER_BINDING),
+                read_framebuffer: get_integer(GL_READ_FRAMEBUFFER_BINDING),
+                unpack_alignment: get_integer(GL_UNPACK_ALIGNMENT),
+                unpack_row_length: get_integer(GL_UNPACK_ROW_LENGTH),
+
+                program: get_integer(GL_CURRENT_PROGRAM),
+                vertex_array: get_integer(GL_VERTEX_ARRAY_BINDING),
+                array_buffer: get_integer(GL_ARRAY_BUFFER_BINDING),
+                active_texture: get_integer(GL_ACTIVE_TEXTURE),
+                texture_2d: 0,
+                sampler: 0,
+            };
+            glGetIntegerv(GL_VIEWPORT, state.viewport.as_mut_ptr());
+            glGetIntegerv(GL_SCISSOR_BOX, state.scissor_box.as_mut_ptr());
+            glGetIntegerv(GL_POLYGON_MODE, state.polygon_mode.as_mut_ptr());
+            glGetBooleanv(GL_COLOR_WRITEMASK, state.color_mask.as_mut_ptr());
+            glGetBooleanv(GL_DEPTH_WRITEMASK, &mut state.depth_mask);
+
+            glActiveTexture(GL_TEXTURE0);
+            state.texture_2d = get_integer(GL_TEXTURE_BINDING_2D);
+            state.sampler = get_integer(GL_SAMPLER_BINDING);
+            glActiveTexture(GLenum(state.active_texture as _));
+            state
+        }
+    }
+
+    pub fn restore(&self) {
+        unsafe {
+            let [x, y, width, height] = self.viewport;
+            glViewport(x, y, width, height);
+            let [x, y, width, height] = self.scissor_box;
+            glScissor(x, y, width, height);
+            glPolygonMode(GL_FRONT_AND_BACK, GLenum(self.polygon_mode[0] as _));
+            let [r, g, b, a] = self.color_mask;
+            glColorMask(r, g, b, a);
+            glDepthMask(self.depth_mask);
+
+            set_enabled(GL_SCISSOR_TEST, self.scissor_test);
+            set_enabled(GL_BLEND, self.blend);
+            set_enabled(GL_DEPTH_TEST, self.depth_test);
+            set_enabled(GL_CULL_FACE, self.cull_face);
+            set_enabled(GL_STENCIL_TEST, self.stencil_test);
+            set_enabled(GL_FRAMEBUFFER_SRGB, self.framebuffer_srgb);
+
+            glBlendFuncSeparate(
+                GLenum(self.blend_src_rgb as _),
+                GLenum(self.blend_dst_rgb as _),
+                GLenum(self.blend_src_alpha as _),
+                GLenum(self.blend_dst_alpha as _)
+            );
+            glBlendEquationSeparate(GLenum(self.blend_equation_rgb as _), GLenum(self.blend_equation_alpha as _));
+
+            glBindFramebuffer(GL_DRAW_FRAMEBUFFER, self.draw_framebuffer as _);
+            glBindFramebuffer(GL_READ_FRAMEBUFFER, self.read_framebuffer as _);
+            glPixelStorei(GL_UNPACK_ALIGNMENT, self.unpack_alignment);
+            glPixelStorei(GL_UNPACK_ROW_LENGTH, self.unpack_row_length);
+
+            glUseProgram(self.program as _);
+            glBindVertexArray(self.vertex_array as _);
+            glBindBuffer(GL_ARRAY_BUFFER, self.array_buffer as _);
+
+            glActiveTexture(GL_TEXTURE0);
+            glBindTexture(GL_TEXTURE_2D, self.texture_2d as _);
+            glBindSampler(0, self.sampler as _);
+            glActiveTexture(GLenum(self.active_texture as _));
+        }
+    }
+}
+
+/// Sets everything egui needs to be drawn correctly, no matter what was set before.
+pub fn set_egui_gl_state(canvas_width: usize, canvas_height: usize) {
+    unsafe {
+        glViewport(0, 0, canvas_width as i32, canvas_height as i32);
+        glEnable(GL_SCISSOR_TEST);
+        glEnable(GL_BLEND);
+        glBlendEquation(GL_FUNC_ADD);
+        glBlendFunc(GL_ONE, GL_ONE_MINUS_SRC_ALPHA);
+        glDisable(GL_DEPTH_TEST);
+        glDisable(GL_CULL_FACE);
+        glDisable(GL_STENCIL_TEST);
+        glEnable(GL_FRAMEBUFFER_SRGB);
+        glPolygonMode(GL_FRONT_AND_BACK, GL_FILL);
+        glColorMask(GL_TRUE.0 as _, GL_TRUE.0 as _, GL_TRUE.0 as _, GL_TRUE.0 as _);
+        glActiveTexture(GL_TEXTURE0);
+        glBindSampler(0, 0);
+    }
+}
+
+unsafe fn get_integer(pname: GLenum) -> i32 {
+    let mut value = 0;
+    glGetIntegerv(pname, &mut value);
+    value
+}
+
+fn set_enabled(cap: GLenum, enabled: bool) {
+    unsafe {
+        if enabled {
+            glEnable(cap);
+        } else {
+            glDisable(cap);
+        }
+    }
+}
@@ -1,16 +1,26 @@
+#[cfg(target_os = "windows")]
 use std::ptr;
 use cli_clipboard::{ClipboardContext, ClipboardProvider};
 
 use egui::{CursorIcon, Event, Modifiers, PlatformOutput, Pos2, pos2, RawInput, Rect, vec2};
 use glfw::{GlfwReceiver, PWindow, WindowEvent};
+#[cfg(target_os = "windows")]
 use winapi::um::winuser;
-use crate::gui::{is_copy_command, is_cut_command, is_paste_command, translate_cursor, translate_modifiers, translate_virtual_key_code};
+#[cfg(target_os = "windows")]
+use crate::gui::translate_win_cursor;
+#[cfg(not(target_os = "windows"))]
+use crate::gui::translate_cursor;
+use crate::gui::{ScrollSettings, ShortcutCommand, ShortcutMap, translate_logical_key, translate_mouse_button, translate_virtual_key_code};
 
 pub struct GuiInput {
     pub raw_input: RawInput,
 
     pub clipboard: Option<ClipboardContext>,
     pub modifiers: Modifiers,
+    pub shortcuts: ShortcutMap,
+    pub scroll: ScrollSettings,
+    /// Every mouse button event of the last `handle_event`, egui ignores buttons above 5 but they are here too.
+    pub mouse_button_events: Vec<(glfw::MouseButton, glfw::Action, glfw::Modifiers)>,
 
     pub focus: bool,
     pub minimized: bool,
@@ -34,6 +44,9 @@ impl GuiInput {
 
             clipboard,
             modifiers: Modifiers::default(),
+            shortcuts: ShortcutMap::default(),
+            scroll: ScrollSettings::default(),
+            mouse_button_events: Vec::new(),
 
             focus: true,
             minimized: true,
@@ -65,10 +78,10 @@ impl GuiInput {
         // let Pos2 { x, y } = self.cursor_pos;
         // window.set_ime_position(winit::dpi::LogicalPosition { x, y });
 
-        self.set_cursor_icon(self.cursor_in_window, window, cursor_icon.clone());
+        self.set_cursor_icon(self.cursor_in_window, window, *cursor_icon);
 
         if !copied_text.is_empty() {
-            self.copy_to_clipboard(&copied_text);
+            self.copy_to_clipboard(copied_text);
         }
 
         if let Some(open_url) = open_url {
@@ -78,7 +91,8 @@ impl GuiInput {
 
     pub fn handle_event(&mut self, window: &mut PWindow, events: &GlfwReceiver<(f64, WindowEvent)>, pixels_per_point: f32) {
         use glfw::WindowEvent::*;
-        for (_, event) in glfw::flush_messages(&events) {
+        self.mouse_button_events.clear();
+        for (_, event) in glfw::flush_messages(events) {
             match event {
                 Close => window.set_should_close(true),
                 _ => {
@@ -92,18 +106,16 @@ impl GuiInput {
                         FramebufferSize(width, height) => {
                             self.raw_input.screen_rect = Some(Rect::from_min_size(Pos2::new(0f32, 0f32), vec2(width as f32, height as f32) / pixels_per_point));
                         }
-                        MouseButton(mouse_btn, action, _) => {
-                            self.raw_input.events.push(egui::Event::PointerButton {
-                                pos: self.cursor_pos,
-                                button: match mouse_btn {
-                                    glfw::MouseButtonLeft => egui::PointerButton::Primary,
-                                    glfw::MouseButtonRight => egui::PointerButton::Secondary,
-                                    glfw::MouseButtonMiddle => egui::PointerButton::Middle,
-                                    _ => unreachable!(),
-                                },
-                                pressed: action == glfw::Action::Press,
-                                modifiers: self.modifiers
-                            });
+                        MouseButton(mouse_btn, action, keymod) => {
+                            self.mouse_button_events.push((mouse_btn, action, keymod));
+                            if let Some(button) = translate_mouse_button(mouse_btn) {
+                                self.raw_input.events.push(egui::Event::PointerButton {
+                                    pos: self.cursor_pos,
+                                    button,
+                                    pressed: action == glfw::Action::Press,
+                                    modifiers: self.modifiers
+                                });
+                            }
                         }
                         CursorPos(x_offset, y_offset) => {
                             self.cursor_pos = pos2(x_offset as f32, y_offset as f32) / pixels_per_point;
@@ -115,42 +127,36 @@ impl GuiInput {
                             self.cursor_in_window = is_entered;
                         }
                         Scroll(x_offset, y_offset) => {
-                            let points_per_scroll_line = 50.0; // Scroll speed decided by consensus: https://github.com/emilk/egui/issues/461
-                            let mut delta = vec2(x_offset as f32, y_offset as f32) * points_per_scroll_line;
-                            delta.x *= -1.0; // Winit has inverted hscroll. Remove this line when we update winit after https://github.com/rust-windowing/winit/pull/2105 is merged and released
-                            if self.modifiers.ctrl || self.modifiers.command {
-                                // Treat as zoom instead:
-                                let factor = (delta.y / 200.0).exp();
-                                self.raw_input.events.push(egui::Event::Zoom(factor));
-                            } else if self.modifiers.shift {
-                                // Treat as horizontal scrolling.
-                                // Note: one Mac we already get horizontal scroll events when shift is down.
-                                self.raw_input
-                                    .events
-                                    .push(Event::Scroll(vec2(delta.x + delta.y, 0.0)));
-                            } else {
-                                self.raw_input.events.push(Event::Scroll(delta));
+                            let mouse_wheel = self.scroll.mouse_wheel(x_offset, y_offset, self.modifiers);
+                            if let Event::MouseWheel { unit, delta, modifiers } = mouse_wheel {
+                                if self.scroll.emit_scroll_events {
+                                    let screen_height = self.raw_input.screen_rect.map_or(0.0, |rect| rect.height());
+                                    self.raw_input.events.push(self.scroll.scroll_event(unit, delta, modifiers, screen_height));
+                                }
                             }
+                            self.raw_input.events.push(mouse_wheel);
                         }
-                        Key(keycode, _scancode, action, keymod) => {
-                            self.modifiers = translate_modifiers(keymod);
-                            let pressed = action == glfw::Action::Press;
+                        Key(keycode, scancode, action, keymod) => {
+                            self.modifiers = self.shortcuts.translate_modifiers(keymod);
+                            // a repeat is a press of a key that is still down
+                            let pressed = action != glfw::Action::Release;
                             let repeat = action == glfw::Action::Repeat;
-                            if pressed {
-                                if is_cut_command(self.modifiers, keycode) {
-                                    self.raw_input.events.push(Event::Cut);
-                                } else if is_copy_command(self.modifiers, keycode) {
-                                    self.raw_input.events.push(Event::Copy);
-                                } else if is_paste_command(self.modifiers, keycode) {
+                            match self.shortcuts.command(keymod, keycode, action) {
+                                Some(ShortcutCommand::Cut) => self.raw_input.events.push(Event::Cut),
+                                Some(ShortcutCommand::Copy) => self.raw_input.events.push(Event::Copy),
+                                Some(ShortcutCommand::Paste) => {
                                     if let Some(content) = self.get_clipboard_content() {
                                         self.raw_input.events.push(Event::Paste(content));
                                     }
                                 }
+                                None => {}
                             }
-                            if let Some(key) = translate_virtual_key_code(keycode) {
+                            // the key code is the position on a US keyboard, the name follows the layout
+                            let key_name = glfw::get_key_name(Some(keycode), Some(scancode));
+                            if let Some(key) = translate_logical_key(keycode, key_name.as_deref()) {
                                 self.raw_input.events.push(Event::Key {
                                     key,
-                                    physical_key: None,
+                                    physical_key: translate_virtual_key_code(keycode),
                                     pressed,
                                     repeat,
                                     modifiers: self.modifiers,
@@ -178,12 +184,13 @@ impl GuiInput {
         }
     }
 
+    #[cfg(target_os = "windows")]
     pub fn set_cursor_icon(&mut self, in_window: bool, window: &mut PWindow, cursor_icon: CursorIcon) {
         self.cursor_current_icon = cursor_icon;
         if cursor_icon == CursorIcon::Default || cursor_icon == CursorIcon::None {
             return;
         }
-        if let Some(cursor) = translate_cursor(cursor_icon) {
+        if let Some(cursor) = translate_win_cursor(cursor_icon) {
             window.set_cursor_mode(glfw::CursorMode::Normal);
             unsafe {
                 if in_window {
@@ -196,6 +203,21 @@ impl GuiInput {
         }
     }
 
+    #[cfg(not(target_os = "windows"))]
+    pub fn set_cursor_icon(&mut self, _in_window: bool, window: &mut PWindow, cursor_icon: CursorIcon) {
+        // glfw keeps the cursor object alive, only replace it when egui asks for another icon
+        if self.cursor_current_icon == cursor_icon {
+            return;
+        }
+        self.cursor_current_icon = cursor_icon;
+        if let Some(cursor) = translate_cursor(cursor_icon) {
+            window.set_cursor_mode(glfw::CursorMode::Normal);
+            window.set_cursor(Some(glfw::Cursor::standard(cursor)));
+        } else {
+            window.set_cursor_mode(glfw::CursorMode::Hidden);
+        }
+    }
+
     pub fn get_clipboard_content(&mut self) -> Option<String> {
         if let Some(clipboard) = self.clipboard.as_mut() {
             if let Ok(content) = clipboard.get_contents() {
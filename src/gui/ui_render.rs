@@ -2,15 +2,17 @@
 use std::collections::HashMap;
 use std::ffi::c_uint;
 use std::mem;
-use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-use egui::{ClippedPrimitive, Color32, ImageData, Mesh, Rect, TextureFilter, TextureId, TextureOptions, TexturesDelta};
-use egui::epaint::{ImageDelta, Primitive};
+use cgmath::Vector2;
+use egui::{ClippedPrimitive, Color32, ImageData, Mesh, Rect, TextureId, TextureOptions, TexturesDelta};
+use egui::epaint::{ImageDelta, PaintCallbackInfo, Primitive};
 use gl33::*;
 use gl33::global_loader::*;
 
-use crate::gui::ui_texture::GuiTexture;
-use crate::shader::Shader;
+use crate::gui::{CallbackFn, GlState, set_egui_gl_state};
+use crate::gui::ui_texture::{GuiTexture, PendingUpload, UserTexture, UserTextureIds};
+use crate::shader::{Shader, ShaderError, TextureUnit};
 
 const POS_SIZE: i32 = 2;
 const TEX_COORDS_SIZE: i32 = 2;
@@ -20,29 +22,100 @@ const POS_OFFSET: i32 = 0;
 const TEX_COORDS_OFFSET: i32 = POS_OFFSET + POS_SIZE * (mem::size_of::<f32>() as i32);
 const COLOR_OFFSET: i32 = TEX_COORDS_OFFSET + TEX_COORDS_SIZE * (mem::size_of::<f32>() as i32);
 
-const VERTEX_SIZE: i32 = POS_SIZE + TEX_COORDS_SIZE + COLOR_SIZE;
 const VERTEX_SIZE_BYTES: i32 = (POS_SIZE + TEX_COORDS_SIZE) * (mem::size_of::<f32>() as i32) + COLOR_SIZE * (mem::size_of::<u8>() as i32);
 
+// compiled into the binary, so it does not depend on the working directory
+const EGUI_SHADER_SRC: &str = include_str!("../../assets/shaders/egui.glsl");
+// files egui.glsl includes, also available to sources passed to `set_shader_source`
+const EGUI_SHADER_INCLUDES: &[(&str, &str)] = &[("srgb.glsl", include_str!("../../assets/shaders/srgb.glsl"))];
+
+// egui meshes can have more than u16::MAX vertices (big plots, long texts)
+type Index = u32;
+const INDEX_TYPE: GLenum = GL_UNSIGNED_INT;
+
+#[repr(C)]
 struct Vertex {
     position: [f32; POS_SIZE as usize],
     coords: [f32; TEX_COORDS_SIZE as usize],
     color: [u8; COLOR_SIZE as usize]
 }
 
+// Where the data of one mesh lives inside the frame buffers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct MeshDraw {
+    index_offset: usize,
+    index_count: usize,
+    base_vertex: usize
+}
+
+// Vertex and index data of all meshes of a frame, uploaded to the gpu at once
+struct MeshBatch {
+    vertices: Vec<Vertex>,
+    indices: Vec<Index>,
+    // one entry for every mesh primitive, in paint order
+    draws: Vec<MeshDraw>
+}
+
+impl MeshBatch {
+    fn from_primitives(clipped_primitives: &[ClippedPrimitive]) -> Self {
+        let (mut vertex_count, mut index_count) = (0, 0);
+        for ClippedPrimitive { primitive, .. } in clipped_primitives {
+            if let Primitive::Mesh(mesh) = primitive {
+                vertex_count += mesh.vertices.len();
+                index_count += mesh.indices.len();
+            }
+        }
+
+        let mut batch = MeshBatch {
+            vertices: Vec::with_capacity(vertex_count),
+            indices: Vec::with_capacity(index_count),
+            draws: Vec::new()
+        };
+        for ClippedPrimitive { primitive, .. } in clipped_primitives {
+            if let Primitive::Mesh(mesh) = primitive {
+                batch.push(mesh);
+            }
+        }
+        batch
+    }
+
+    fn push(&mut self, mesh: &Mesh) {
+        // indices stay relative to the mesh, the draw call adds the base vertex
+        self.draws.push(MeshDraw {
+            index_offset: self.indices.len(),
+            index_count: mesh.indices.len(),
+            base_vertex: self.vertices.len()
+        });
+        self.vertices.extend(mesh.vertices.iter().map(|v| Vertex {
+            position: [v.pos.x, v.pos.y],
+            coords: [v.uv.x, v.uv.y],
+            color: v.color.to_array(),
+        }));
+        self.indices.extend(mesh.indices.iter().map(|idx| *idx as Index));
+    }
+}
+
 pub struct GuiRender {
     shader: Shader,
     vao_id: c_uint,
     vbo_id: c_uint,
     ebo_id: c_uint,
+    // allocated buffer sizes in bytes, the buffers only grow
+    vbo_capacity: usize,
+    ebo_capacity: usize,
 
     canvas_width: usize,
     canvas_height: usize,
-    textures: HashMap<TextureId, GuiTexture>
+    textures: HashMap<TextureId, GuiTexture>,
+    user_texture_ids: UserTextureIds,
+
+    // restore the caller's gl state after rendering
+    preserve_gl_state: bool
 }
 
 impl GuiRender {
     pub fn new(width: usize, height: usize) -> Self {
-        let shader = Shader::new("assets/shaders/egui.glsl");
+        let shader = Shader::from_embedded_source("egui.glsl", EGUI_SHADER_SRC, EGUI_SHADER_INCLUDES).expect("Error: the embedded egui shader failed to build");
         unsafe {
             let mut vao_id = 0;
             glGenVertexArrays(1, &mut vao_id);
@@ -57,16 +130,34 @@ impl GuiRender {
             glGenBuffers(1, &mut ebo_id);
             assert_ne!(ebo_id, 0);
 
+            // vertex layout, the element buffer binding is part of the vao state
+            glBindBuffer(GL_ARRAY_BUFFER, vbo_id);
+            glBindBuffer(GL_ELEMENT_ARRAY_BUFFER, ebo_id);
+            glVertexAttribPointer(0, POS_SIZE, GL_FLOAT, GL_FALSE.0 as _, VERTEX_SIZE_BYTES, POS_OFFSET as *const _);
+            glEnableVertexAttribArray(0);
+            glVertexAttribPointer(1, TEX_COORDS_SIZE, GL_FLOAT, GL_FALSE.0 as _, VERTEX_SIZE_BYTES, TEX_COORDS_OFFSET as *const _);
+            glEnableVertexAttribArray(1);
+            glVertexAttribPointer(2, COLOR_SIZE, GL_UNSIGNED_BYTE, GL_FALSE.0 as _, VERTEX_SIZE_BYTES, COLOR_OFFSET as *const _);
+            glEnableVertexAttribArray(2);
+
+            glBindVertexArray(0);
+            glBindBuffer(GL_ARRAY_BUFFER, 0);
+
             GuiRender {
                 shader,
                 vao_id,
                 vbo_id,
                 ebo_id,
+                vbo_capacity: 0,
+                ebo_capacity: 0,
 
                 canvas_width: width,
                 canvas_height: height,
 
                 textures: Default::default(),
+                user_texture_ids: UserTextureIds::default(),
+
+                preserve_gl_state: false,
             }
         }
     }
@@ -74,10 +165,36 @@ impl GuiRender {
     pub fn set_size(&mut self, width: usize, height: usize) {
         (self.canvas_width, self.canvas_height) = (width, height);
     }
+
+    // Replaces the embedded egui shader, it has to keep the attribute locations and uniforms of assets/shaders/egui.glsl.
+    // On error the current shader stays in use.
+    pub fn set_shader_file(&mut self, file_path: &str) -> Result<(), ShaderError> {
+        // the old program is deleted when it drops
+        self.shader = Shader::new(file_path)?;
+        Ok(())
+    }
+
+    pub fn set_shader_source(&mut self, source: &str) -> Result<(), ShaderError> {
+        self.shader = Shader::from_embedded_source("custom egui shader", source, EGUI_SHADER_INCLUDES)?;
+        Ok(())
+    }
+
+    // Opt-in, useful when egui is drawn on top of an engine that keeps its own pipeline state
+    pub fn set_preserve_gl_state(&mut self, preserve_gl_state: bool) {
+        self.preserve_gl_state = preserve_gl_state;
+    }
 }
 
 impl GuiRender {
     pub fn render(&mut self, pixels_per_point: f32, clipped_primitives: &[ClippedPrimitive], textures_delta: &TexturesDelta) {
+        let saved_state = self.preserve_gl_state.then(GlState::capture);
+        unsafe {
+            // uploads bind to the active texture unit, keep them on the unit GlState restores
+            glActiveTexture(GL_TEXTURE0);
+            // texture rows are tightly packed RGBA, whatever the host set up for its own uploads
+            glPixelStorei(GL_UNPACK_ALIGNMENT, 4);
+            glPixelStorei(GL_UNPACK_ROW_LENGTH, 0);
+        }
         // 3. Get textures to be rendered from egui_ctx, bind and upload to GPU
         for (id, image_delta) in &textures_delta.set {
             self.upload_egui_texture(*id, image_delta);
@@ -90,39 +207,106 @@ impl GuiRender {
         for id in &textures_delta.free {
             self.free_texture(id);
         }
+        self.free_dropped_textures();
+        if let Some(saved_state) = saved_state {
+            saved_state.restore();
+        }
     }
 }
 
 impl GuiRender {
-    pub fn new_texture(&mut self, size: (usize, usize), srgba_pixels: &[Color32], options: TextureOptions) -> egui::TextureId {
+    pub fn new_texture(&mut self, size: (usize, usize), srgba_pixels: &[Color32], options: TextureOptions) -> UserTexture {
         assert_eq!(size.0 * size.1, srgba_pixels.len());
 
-        let pixels: Vec<u8> = srgba_pixels.iter().flat_map(|a| a.to_array()).collect();
-        let id = TextureId::User(self.textures.len() as u64);
-        self.textures.insert(
-            id,
-            GuiTexture::new(
-                0,
-                options,
-                [size.0, size.1],
-                pixels,
-                true
-            ),
-        );
+        let handle = self.user_texture_ids.next_handle();
+
+        let mut texture = GuiTexture::new(0, options, [size.0, size.1]);
+        texture.set_pixels([size.0, size.1], to_rgba_bytes(srgba_pixels));
+        self.textures.insert(handle.id(), texture);
+        handle
+    }
+
+    pub fn update_texture(&mut self, texture: &UserTexture, pixels: &[Color32]) {
+        let texture = self.user_texture_mut(texture);
+        let [width, height] = texture.size();
+        assert_eq!(width * height, pixels.len(), "Mismatch between texture size and texel count");
+        texture.set_pixels([width, height], to_rgba_bytes(pixels));
+    }
+
+    pub fn update_texture_region(&mut self, texture: &UserTexture, pos: [usize; 2], size: [usize; 2], pixels: &[Color32]) {
+        let texture = self.user_texture_mut(texture);
+        assert_eq!(size[0] * size[1], pixels.len(), "Mismatch between region size and texel count");
+        let texture_size = texture.size();
+        assert!(pos[0] + size[0] <= texture_size[0] && pos[1] + size[1] <= texture_size[1], "Region is outside of the texture");
+        texture.set_sub_pixels(pos, size, to_rgba_bytes(pixels));
+    }
+
+    pub fn resize_texture(&mut self, texture: &UserTexture, size: [usize; 2], pixels: &[Color32]) {
+        let texture = self.user_texture_mut(texture);
+        assert_eq!(size[0] * size[1], pixels.len(), "Mismatch between texture size and texel count");
+        texture.set_pixels(size, to_rgba_bytes(pixels));
+    }
+
+    // The gl texture stays owned by the caller, it is sampled with `options` but never modified or deleted
+    pub fn register_native_texture(&mut self, gl_id: c_uint, size: [usize; 2], options: TextureOptions) -> TextureId {
+        let id = self.user_texture_ids.next_id();
+        self.textures.insert(id, GuiTexture::new_native(gl_id, options, size));
         id
     }
 
-    pub fn update_texture(&mut self, texture_id: &TextureId, pixels: &[Color32]) {
-        let texture = self
-            .textures
-            .get_mut(texture_id)
+    // Unregistered when the handle is dropped, used by RenderTarget
+    pub(crate) fn register_native_texture_handle(&mut self, gl_id: c_uint, size: [usize; 2], options: TextureOptions) -> UserTexture {
+        let handle = self.user_texture_ids.next_handle();
+        self.textures.insert(handle.id(), GuiTexture::new_native(gl_id, options, size));
+        handle
+    }
+
+    pub fn replace_native_texture(&mut self, id: TextureId, gl_id: c_uint, size: [usize; 2]) {
+        let options = self.native_texture(&id).options();
+        let old_texture = self.textures.insert(id, GuiTexture::new_native(gl_id, options, size));
+        if let Some(old_texture) = old_texture {
+            old_texture.free();
+        }
+    }
+
+    pub fn unregister_native_texture(&mut self, id: TextureId) {
+        self.native_texture(&id);
+        self.free_texture(&id);
+    }
+
+    fn native_texture(&self, id: &TextureId) -> &GuiTexture {
+        let texture = self.textures.get(id).expect("Texture with id has not been registered");
+        assert!(texture.is_native(), "Texture with id is not a native texture");
+        texture
+    }
+
+    pub fn free_user_texture(&mut self, texture: UserTexture) {
+        drop(texture);
+        self.free_dropped_textures();
+    }
+
+    fn user_texture_mut(&mut self, texture: &UserTexture) -> &mut GuiTexture {
+        let texture = self.textures
+            .get_mut(&texture.id())
             .expect("Texture with id has not been created");
+        assert!(!texture.is_native(), "Native textures can not be updated through GuiRender");
+        texture
+    }
 
-        texture.set_pixels(pixels.iter().flat_map(|a| a.to_array()).collect());
-        texture.set_dirty(true);
+    fn free_dropped_textures(&mut self) {
+        let textures = &mut self.textures;
+        self.user_texture_ids.free_dropped(|id| {
+            if let Some(texture) = textures.remove(&id) {
+                texture.free();
+            }
+        });
     }
 }
 
+fn to_rgba_bytes(pixels: &[Color32]) -> Vec<u8> {
+    pixels.iter().flat_map(|a| a.to_array()).collect()
+}
+
 impl GuiRender {
     pub fn upload_egui_texture(&mut self, id: TextureId, delta: &ImageDelta) {
         let options = delta.options;
@@ -153,12 +337,10 @@ impl GuiRender {
             return;
         }
         // one picture as whole texture
-        let mut texture = GuiTexture::new(
+        let texture = GuiTexture::new(
             0,
             options,
-            [texture_width, texture_height],
-            vec![],
-            false
+            [texture_width, texture_height]
         );
         texture.gen_tex_and_bind();
         texture.upload(data);
@@ -175,9 +357,12 @@ impl GuiRender {
             if texture.texture_id() == 0 {
                 texture.gen_tex_and_bind();
             }
-            let data = texture.take_pixels();
-            texture.upload(data);
-            texture.set_dirty(false);
+            for PendingUpload { pos, size, pixels } in texture.take_pending() {
+                match pos {
+                    Some([x_offset, y_offset]) => texture.upload_sub(x_offset, y_offset, size[0], size[1], pixels),
+                    None => texture.upload(pixels)
+                }
+            }
         }
     }
 
@@ -189,99 +374,194 @@ impl GuiRender {
 }
 
 impl GuiRender {
-    fn paint(&self, pixels_per_point: f32, clipped_primitives: &[ClippedPrimitive]) {
-        unsafe {
-            glEnable(GL_SCISSOR_TEST);
+    fn paint(&mut self, pixels_per_point: f32, clipped_primitives: &[ClippedPrimitive]) {
+        self.prepare_painting(pixels_per_point);
 
-            // bind shader
-            self.shader.attach();
-            glActiveTexture(GL_TEXTURE0);
-            // upload uniform
-            let screen_size_points = egui::vec2(self.canvas_width as f32, self.canvas_height as f32) / pixels_per_point;
-            let u_screen_size_loc = self.shader.get_uniform_location("uScreenSize");
-            glUniform2f(u_screen_size_loc, screen_size_points.x, screen_size_points.y);
-            // upload uniform
-            let u_sampler_loc = self.shader.get_uniform_location("uSampler");
-            glUniform1i(u_sampler_loc, 0);
-            glViewport(0, 0, self.canvas_width as i32, self.canvas_height as i32);
-        }
+        let batch = MeshBatch::from_primitives(clipped_primitives);
+        self.upload_batch(&batch);
+        let mut draws = batch.draws.iter();
 
         for ClippedPrimitive { clip_rect, primitive} in clipped_primitives {
+            self.set_clip_rect(clip_rect, pixels_per_point);
             match primitive {
                 Primitive::Mesh(mesh) => {
-                    self.paint_mesh(mesh, clip_rect, pixels_per_point);
+                    let draw = draws.next().expect("Every mesh has a draw in the batch");
+                    self.paint_mesh(mesh, draw);
                 }
-                Primitive::Callback(_) => {
-                    panic!("Custom rendering callbacks are not implemented in egui_glium");
+                Primitive::Callback(callback) => {
+                    if !callback.rect.is_positive() {
+                        continue;
+                    }
+                    let info = PaintCallbackInfo {
+                        viewport: callback.rect,
+                        clip_rect: *clip_rect,
+                        pixels_per_point,
+                        screen_size_px: [self.canvas_width as u32, self.canvas_height as u32],
+                    };
+                    let viewport_px = info.viewport_in_pixels();
+                    unsafe {
+                        glViewport(viewport_px.left_px, viewport_px.from_bottom_px, viewport_px.width_px, viewport_px.height_px);
+                    }
+                    if let Some(callback) = callback.callback.downcast_ref::<CallbackFn>() {
+                        callback.call(info);
+                    } else {
+                        static WARNED: AtomicBool = AtomicBool::new(false);
+                        if !WARNED.swap(true, Ordering::Relaxed) {
+                            eprintln!("Warning: Unsupported render callback. Expected egui_glfw_gl2::gui::CallbackFn");
+                        }
+                    }
+                    // the callback may have changed anything
+                    self.prepare_painting(pixels_per_point);
                 }
             }
         }
 
         unsafe {
             glDisable(GL_SCISSOR_TEST);
+            glBindSampler(0, 0);
+        }
+    }
+
+    fn prepare_painting(&self, pixels_per_point: f32) {
+        set_egui_gl_state(self.canvas_width, self.canvas_height);
+        // bind shader
+        self.shader.attach();
+        // upload uniforms
+        let screen_size_points = egui::vec2(self.canvas_width as f32, self.canvas_height as f32) / pixels_per_point;
+        self.shader.set("uScreenSize", Vector2::new(screen_size_points.x, screen_size_points.y));
+        self.shader.set("uSampler", TextureUnit(0));
+
+        glBindVertexArray(self.vao_id);
+    }
+
+    fn set_clip_rect(&self, clip_rect: &Rect, pixels_per_point: f32) {
+        let screen_size_pixels = egui::vec2(self.canvas_width as f32, self.canvas_height as f32);
+        // Transform clip rect to physical pixels:
+        let clip_min_x = pixels_per_point * clip_rect.min.x;
+        let clip_min_y = pixels_per_point * clip_rect.min.y;
+        let clip_max_x = pixels_per_point * clip_rect.max.x;
+        let clip_max_y = pixels_per_point * clip_rect.max.y;
+        // Clamp:
+        let clip_min_x = clip_min_x.clamp(0.0, screen_size_pixels.x);
+        let clip_min_y = clip_min_y.clamp(0.0, screen_size_pixels.y);
+        let clip_max_x = clip_max_x.clamp(clip_min_x, screen_size_pixels.x);
+        let clip_max_y = clip_max_y.clamp(clip_min_y, screen_size_pixels.y);
+        // Round to integer:
+        let clip_min_x = clip_min_x.round() as i32;
+        let clip_min_y = clip_min_y.round() as i32;
+        let clip_max_x = clip_max_x.round() as i32;
+        let clip_max_y = clip_max_y.round() as i32;
+
+        //scissor Y coordinate is from the bottom
+        unsafe {
+            glScissor(clip_min_x, self.canvas_height as i32 - clip_max_y, clip_max_x - clip_min_x, clip_max_y - clip_min_y, );
         }
     }
 
-    fn paint_mesh(&self, mesh: &Mesh, clip_rect: &Rect, pixels_per_point: f32) {
+    fn upload_batch(&mut self, batch: &MeshBatch) {
+        unsafe {
+            glBindVertexArray(self.vao_id);
+            glBindBuffer(GL_ARRAY_BUFFER, self.vbo_id);
+            upload_buffer(GL_ARRAY_BUFFER, &mut self.vbo_capacity, &batch.vertices);
+            // the vao is bound, so this is the ebo of the vao
+            upload_buffer(GL_ELEMENT_ARRAY_BUFFER, &mut self.ebo_capacity, &batch.indices);
+        }
+    }
+
+    fn paint_mesh(&self, mesh: &Mesh, draw: &MeshDraw) {
         debug_assert!(mesh.is_valid());
 
         if let Some(texture) = self.textures.get(&mesh.texture_id) {
             unsafe {
                 glBindTexture(GL_TEXTURE_2D, texture.texture_id());
+                glBindSampler(0, texture.sampler_id());
+                glDrawElementsBaseVertex(
+                    GL_TRIANGLES,
+                    draw.index_count as _,
+                    INDEX_TYPE,
+                    (draw.index_offset * mem::size_of::<Index>()) as *const _,
+                    draw.base_vertex as _
+                );
             }
+        }
+    }
+}
 
-            let screen_size_pixels = egui::vec2(self.canvas_width as f32, self.canvas_height as f32);
-            // Transform clip rect to physical pixels:
-            let clip_min_x = pixels_per_point * clip_rect.min.x;
-            let clip_min_y = pixels_per_point * clip_rect.min.y;
-            let clip_max_x = pixels_per_point * clip_rect.max.x;
-            let clip_max_y = pixels_per_point * clip_rect.max.y;
-            // Clamp:
-            let clip_min_x = clip_min_x.clamp(0.0, screen_size_pixels.x);
-            let clip_min_y = clip_min_y.clamp(0.0, screen_size_pixels.y);
-            let clip_max_x = clip_max_x.clamp(clip_min_x, screen_size_pixels.x);
-            let clip_max_y = clip_max_y.clamp(clip_min_y, screen_size_pixels.y);
-            // Round to integer:
-            let clip_min_x = clip_min_x.round() as i32;
-            let clip_min_y = clip_min_y.round() as i32;
-            let clip_max_x = clip_max_x.round() as i32;
-            let clip_max_y = clip_max_y.round() as i32;
-
-            //scissor Y coordinate is from the bottom
-            unsafe {
-                glScissor(clip_min_x, self.canvas_height as i32 - clip_max_y, clip_max_x - clip_min_x, clip_max_y - clip_min_y, );
-
-                glBindVertexArray(self.vao_id);
-
-                let mut vertices: Vec<Vertex> = Vec::with_capacity(mesh.vertices.len());
-                for v in &mesh.vertices {
-                    vertices.push(Vertex{
-                        position: [v.pos.x, v.pos.y],
-                        coords: [v.uv.x, v.uv.y],
-                        color: v.color.to_array(),
-                    });
-                }
-
-                glBindBuffer(GL_ARRAY_BUFFER, self.vbo_id);
-                glBufferData(GL_ARRAY_BUFFER, mem::size_of_val(vertices.deref()) as isize, vertices.as_ptr().cast(), GL_STREAM_DRAW);
+impl Drop for GuiRender {
+    fn drop(&mut self) {
+        for (_, texture) in self.textures.drain() {
+            texture.free();
+        }
+        unsafe {
+            glDeleteBuffers(1, &self.vbo_id);
+            glDeleteBuffers(1, &self.ebo_id);
+            glDeleteVertexArrays(1, &self.vao_id);
+        }
+    }
+}
 
-                let indices: Vec<u16> = mesh.indices.iter().map(move |idx| *idx as u16).collect();
-                glBindBuffer(GL_ELEMENT_ARRAY_BUFFER, self.ebo_id);
-                glBufferData(GL_ELEMENT_ARRAY_BUFFER, mem::size_of_val(indices.deref()) as isize, indices.as_ptr().cast(), GL_STREAM_DRAW);
+// Orphans the bound buffer every frame so the driver does not have to wait for the previous draws,
+// and grows it when the data no longer fits.
+unsafe fn upload_buffer<T>(target: GLenum, capacity: &mut usize, data: &[T]) {
+    let size = mem::size_of_val(data);
+    if size > *capacity {
+        *capacity = size.next_power_of_two();
+    }
+    if *capacity == 0 {
+        return;
+    }
+    glBufferData(target, *capacity as isize, core::ptr::null(), GL_STREAM_DRAW);
+    glBufferSubData(target, 0, size as isize, data.as_ptr().cast());
+}
 
-                glVertexAttribPointer(0, POS_SIZE, GL_FLOAT, GL_FALSE.0 as _, VERTEX_SIZE_BYTES, POS_OFFSET as *const _);
-                glEnableVertexAttribArray(0);
-                glVertexAttribPointer(1, TEX_COORDS_SIZE, GL_FLOAT, GL_FALSE.0 as _, VERTEX_SIZE_BYTES, TEX_COORDS_OFFSET as *const _);
-                glEnableVertexAttribArray(1);
-                glVertexAttribPointer(2, COLOR_SIZE, GL_UNSIGNED_BYTE, GL_FALSE.0 as _, VERTEX_SIZE_BYTES, COLOR_OFFSET as *const _);
-                glEnableVertexAttribArray(2);
+#[cfg(test)]
+mod tests {
+    use egui::{Color32, pos2, Rect, Shape, Stroke, TextureId};
+    use egui::epaint::{ClippedPrimitive, ClippedShape, Mesh, Primitive, TessellationOptions, Tessellator};
+
+    use super::{MeshBatch, MeshDraw};
+
+    #[test]
+    fn mesh_batch_keeps_indices_past_u16_limit() {
+        // a long anti-aliased line ends up as one mesh with several vertices per point
+        let points = (0..40_000).map(|i| pos2(i as f32 * 0.1, (i % 2) as f32 * 10.0)).collect();
+        let shape = Shape::line(points, Stroke::new(1.0, Color32::WHITE));
+        let primitives = Tessellator::new(1.0, TessellationOptions::default(), [1, 1], vec![])
+            .tessellate_shapes(vec![ClippedShape { clip_rect: Rect::EVERYTHING, shape }]);
+        assert_eq!(primitives.len(), 1);
+        let Primitive::Mesh(mesh) = &primitives[0].primitive else {
+            panic!("Expected a mesh primitive");
+        };
+        assert!(mesh.vertices.len() > u16::MAX as usize);
 
-                glDrawElements(GL_TRIANGLES, indices.len() as _, GL_UNSIGNED_SHORT, core::ptr::null());
+        let batch = MeshBatch::from_primitives(&primitives);
+        assert_eq!(batch.vertices.len(), mesh.vertices.len());
+        assert_eq!(batch.indices, mesh.indices);
+        assert!(batch.indices.iter().any(|&idx| idx > u16::MAX as u32));
+        assert!(batch.indices.iter().all(|&idx| (idx as usize) < batch.vertices.len()));
+    }
 
-                glDisableVertexAttribArray(0);
-                glDisableVertexAttribArray(1);
-                glDisableVertexAttribArray(2);
-            }
-        }
+    #[test]
+    fn mesh_batch_offsets_follow_paint_order() {
+        let mut first = Mesh::with_texture(TextureId::Managed(0));
+        first.add_colored_rect(Rect::from_min_max(pos2(0.0, 0.0), pos2(10.0, 10.0)), Color32::RED);
+        let mut second = Mesh::with_texture(TextureId::User(0));
+        second.add_colored_rect(Rect::from_min_max(pos2(5.0, 5.0), pos2(20.0, 20.0)), Color32::BLUE);
+        second.add_colored_rect(Rect::from_min_max(pos2(0.0, 5.0), pos2(20.0, 20.0)), Color32::GREEN);
+
+        let primitives = [first, second].map(|mesh| ClippedPrimitive {
+            clip_rect: Rect::EVERYTHING,
+            primitive: Primitive::Mesh(mesh)
+        });
+        let batch = MeshBatch::from_primitives(&primitives);
+
+        assert_eq!(batch.vertices.len(), 12);
+        assert_eq!(batch.indices.len(), 18);
+        assert_eq!(batch.draws, vec![
+            MeshDraw { index_offset: 0, index_count: 6, base_vertex: 0 },
+            MeshDraw { index_offset: 6, index_count: 12, base_vertex: 4 },
+        ]);
+        // indices stay relative to their own mesh
+        assert_eq!(&batch.indices[6..12], &batch.indices[0..6]);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,246 @@
+use std::ffi::c_uint;
+
+use egui::{TextureId, TextureOptions};
+use egui::load::SizedTexture;
+use gl33::*;
+use gl33::global_loader::*;
+
+use crate::gui::{GuiRender, UserTexture};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderTargetOptions {
+    // attach a depth24/stencil8 renderbuffer
+    pub depth_stencil: bool,
+    // > 1 renders into multisampled buffers and resolves into the texture egui samples
+    pub samples: u32,
+    pub texture_options: TextureOptions
+}
+
+impl Default for RenderTargetOptions {
+    fn default() -> Self {
+        Self {
+            depth_stencil: true,
+            samples: 1,
+            texture_options: TextureOptions::LINEAR
+        }
+    }
+}
+
+/// An offscreen framebuffer whose color texture can be shown with `egui::Image`,
+/// e.g. a 3d viewport inside a resizable egui window.
+pub struct RenderTarget {
+    options: RenderTargetOptions,
+    size: [usize; 2],
+    texture: UserTexture,
+    buffers: Buffers
+}
+
+#[derive(Clone, Copy)]
+struct Buffers {
+    // the framebuffer egui samples from, only the color texture when multisampled
+    fbo_id: c_uint,
+    color_texture_id: c_uint,
+    // framebuffer drawn into when multisampled
+    msaa_fbo_id: c_uint,
+    msaa_color_rbo_id: c_uint,
+    depth_stencil_rbo_id: c_uint,
+}
+
+impl RenderTarget {
+    pub fn new(gui_render: &mut GuiRender, size: [usize; 2], options: RenderTargetOptions) -> Self {
+        let size = clamp_size(size);
+        let buffers = Buffers::create(size, &options);
+        RenderTarget {
+            options,
+            size,
+            texture: gui_render.register_native_texture_handle(buffers.color_texture_id, size, options.texture_options),
+            buffers
+        }
+    }
+
+    pub fn texture_id(&self) -> TextureId {
+        self.texture.id()
+    }
+
+    pub fn size(&self) -> [usize; 2] {
+        self.size
+    }
+
+    pub fn resize(&mut self, gui_render: &mut GuiRender, size: [usize; 2]) {
+        let Some(size) = changed_size(self.size, size) else {
+            return;
+        };
+        self.size = size;
+        self.buffers.delete();
+        self.buffers = Buffers::create(size, &self.options);
+        gui_render.replace_native_texture(self.texture.id(), self.buffers.color_texture_id, size);
+    }
+
+    /// Resizes the target to the space left in `ui` and shows it there.
+    pub fn show(&mut self, ui: &mut egui::Ui, gui_render: &mut GuiRender) -> egui::Response {
+        let size_points = ui.available_size();
+        self.resize(gui_render, size_in_pixels(size_points, ui.ctx().pixels_per_point()));
+        ui.image(SizedTexture::new(self.texture.id(), size_points))
+    }
+
+    /// Draws into the target, `draw` gets the size in pixels. Framebuffer and viewport are restored afterwards.
+    pub fn draw(&self, draw: impl FnOnce([usize; 2])) {
+        let [width, height] = self.size;
+        let buffers = &self.buffers;
+        unsafe {
+            let mut previous_fbo = 0;
+            glGetIntegerv(GL_FRAMEBUFFER_BINDING, &mut previous_fbo);
+            let mut previous_viewport = [0; 4];
+            glGetIntegerv(GL_VIEWPORT, previous_viewport.as_mut_ptr());
+
+            let draw_fbo = if buffers.msaa_fbo_id != 0 { buffers.msaa_fbo_id } else { buffers.fbo_id };
+            glBindFramebuffer(GL_FRAMEBUFFER, draw_fbo);
+            glViewport(0, 0, width as _, height as _);
+
+            draw(self.size);
+
+            if buffers.msaa_fbo_id != 0 {
+                // resolve into the texture egui samples
+                glBindFramebuffer(GL_READ_FRAMEBUFFER, buffers.msaa_fbo_id);
+                glBindFramebuffer(GL_DRAW_FRAMEBUFFER, buffers.fbo_id);
+                glBlitFramebuffer(
+                    0, 0, width as _, height as _,
+                    0, 0, width as _, height as _,
+                    GL_COLOR_BUFFER_BIT,
+                    GL_NEAREST
+                );
+            }
+
+            glBindFramebuffer(GL_FRAMEBUFFER, previous_fbo as _);
+            let [x, y, width, height] = previous_viewport;
+            glViewport(x, y, width, height);
+        }
+    }
+}
+
+// framebuffers can't be empty
+fn clamp_size(size: [usize; 2]) -> [usize; 2] {
+    [size[0].max(1), size[1].max(1)]
+}
+
+// the size to resize to, None if the buffers already have it
+fn changed_size(current: [usize; 2], requested: [usize; 2]) -> Option<[usize; 2]> {
+    let size = clamp_size(requested);
+    (size != current).then_some(size)
+}
+
+// a negative size, e.g. of a ui with no space left, becomes 0
+fn size_in_pixels(size_points: egui::Vec2, pixels_per_point: f32) -> [usize; 2] {
+    [(size_points.x * pixels_per_point).round() as usize, (size_points.y * pixels_per_point).round() as usize]
+}
+
+impl Buffers {
+    fn create(size: [usize; 2], options: &RenderTargetOptions) -> Self {
+        let [width, height] = size;
+        let samples = options.samples as i32;
+        let mut buffers = Buffers {
+            fbo_id: 0,
+            color_texture_id: 0,
+            msaa_fbo_id: 0,
+            msaa_color_rbo_id: 0,
+            depth_stencil_rbo_id: 0,
+        };
+        unsafe {
+            glGenTextures(1, &mut buffers.color_texture_id);
+            assert_ne!(buffers.color_texture_id, 0);
+            glBindTexture(GL_TEXTURE_2D, buffers.color_texture_id);
+            glTexImage2D(
+                GL_TEXTURE_2D,
+                0,
+                GL_SRGB8_ALPHA8.0 as _,
+                width as _,
+                height as _,
+                0,
+                GL_RGBA,
+                GL_UNSIGNED_BYTE,
+                core::ptr::null()
+            );
+            glBindTexture(GL_TEXTURE_2D, 0);
+
+            glGenFramebuffers(1, &mut buffers.fbo_id);
+            assert_ne!(buffers.fbo_id, 0);
+            glBindFramebuffer(GL_FRAMEBUFFER, buffers.fbo_id);
+            glFramebufferTexture2D(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT0, GL_TEXTURE_2D, buffers.color_texture_id, 0);
+
+            if samples > 1 {
+                glGenFramebuffers(1, &mut buffers.msaa_fbo_id);
+                assert_ne!(buffers.msaa_fbo_id, 0);
+                glBindFramebuffer(GL_FRAMEBUFFER, buffers.msaa_fbo_id);
+
+                glGenRenderbuffers(1, &mut buffers.msaa_color_rbo_id);
+                glBindRenderbuffer(GL_RENDERBUFFER, buffers.msaa_color_rbo_id);
+                glRenderbufferStorageMultisample(GL_RENDERBUFFER, samples, GL_SRGB8_ALPHA8, width as _, height as _);
+                glFramebufferRenderbuffer(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT0, GL_RENDERBUFFER, buffers.msaa_color_rbo_id);
+            }
+
+            // depth and stencil belong to the framebuffer that is drawn into
+            if options.depth_stencil {
+                glGenRenderbuffers(1, &mut buffers.depth_stencil_rbo_id);
+                glBindRenderbuffer(GL_RENDERBUFFER, buffers.depth_stencil_rbo_id);
+                if samples > 1 {
+                    glRenderbufferStorageMultisample(GL_RENDERBUFFER, samples, GL_DEPTH24_STENCIL8, width as _, height as _);
+                } else {
+                    glRenderbufferStorage(GL_RENDERBUFFER, GL_DEPTH24_STENCIL8, width as _, height as _);
+                }
+                glFramebufferRenderbuffer(GL_FRAMEBUFFER, GL_DEPTH_STENCIL_ATTACHMENT, GL_RENDERBUFFER, buffers.depth_stencil_rbo_id);
+            }
+            glBindRenderbuffer(GL_RENDERBUFFER, 0);
+
+            assert_eq!(glCheckFramebufferStatus(GL_FRAMEBUFFER), GL_FRAMEBUFFER_COMPLETE, "Error: render target framebuffer is incomplete");
+            glBindFramebuffer(GL_FRAMEBUFFER, 0);
+        }
+        buffers
+    }
+
+    fn delete(&self) {
+        unsafe {
+            glDeleteFramebuffers(1, &self.fbo_id);
+            glDeleteTextures(1, &self.color_texture_id);
+            if self.msaa_fbo_id != 0 {
+                glDeleteFramebuffers(1, &self.msaa_fbo_id);
+                glDeleteRenderbuffers(1, &self.msaa_color_rbo_id);
+            }
+            if self.depth_stencil_rbo_id != 0 {
+                glDeleteRenderbuffers(1, &self.depth_stencil_rbo_id);
+            }
+        }
+    }
+}
+
+impl Drop for RenderTarget {
+    // egui may still draw the texture this frame, the buffers are deleted once the dropped texture
+    // handle is unregistered from GuiRender during the next render, or when the GuiRender is dropped
+    fn drop(&mut self) {
+        let buffers = self.buffers;
+        self.texture.release_with(move || buffers.delete());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use egui::vec2;
+
+    use super::{changed_size, size_in_pixels};
+
+    #[test]
+    fn resizes_only_to_new_non_empty_sizes() {
+        assert_eq!(changed_size([320, 240], [320, 240]), None);
+        assert_eq!(changed_size([320, 240], [640, 240]), Some([640, 240]));
+        assert_eq!(changed_size([320, 240], [0, 0]), Some([1, 1]));
+        assert_eq!(changed_size([1, 1], [0, 0]), None);
+        assert_eq!(changed_size([1, 1], [0, 5]), Some([1, 5]));
+    }
+
+    #[test]
+    fn shows_the_available_space_in_pixels() {
+        assert_eq!(size_in_pixels(vec2(320.0, 240.0), 1.0), [320, 240]);
+        assert_eq!(size_in_pixels(vec2(100.4, 50.6), 1.5), [151, 76]);
+        assert_eq!(size_in_pixels(vec2(-3.0, 10.0), 2.0), [0, 20]);
+        assert_eq!(changed_size([8, 8], size_in_pixels(vec2(-3.0, 10.0), 2.0)), Some([1, 20]));
+    }
+}
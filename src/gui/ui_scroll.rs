@@ -0,0 +1,119 @@
+use egui::{Event, Modifiers, MouseWheelUnit, Vec2, vec2};
+
+/// How GLFW scroll offsets become `egui::Event::MouseWheel`.
+///
+/// A wheel reports whole lines, touchpads report fractions of them, which makes them the only way to tell the two apart.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScrollSettings {
+    /// Lines per offset of a wheel, GLFW reports one per notch.
+    pub line_scale: f32,
+    /// Points per offset of a touchpad, GLFW divides the pixel deltas of macOS and Wayland by 10.
+    pub pixel_scale: f32,
+    /// Scroll fractional offsets by points instead of lines.
+    ///
+    /// A touchpad offset that happens to be whole, e.g. `1.0`, can't be told apart from a wheel notch and scrolls a line.
+    pub detect_smooth_scroll: bool,
+    /// Also push the `Event::Scroll` or `Event::Zoom` egui 0.27 scrolls on, it ignores `Event::MouseWheel`.
+    ///
+    /// Turn it off with an egui that handles `Event::MouseWheel` itself, or every scroll is applied twice.
+    pub emit_scroll_events: bool,
+    /// Points per line of the `Event::Scroll` fallback.
+    pub points_per_line: f32
+}
+
+impl Default for ScrollSettings {
+    fn default() -> Self {
+        ScrollSettings {
+            line_scale: 1.0,
+            pixel_scale: 10.0,
+            detect_smooth_scroll: true,
+            emit_scroll_events: true,
+            // scroll speed decided by consensus: https://github.com/emilk/egui/issues/461
+            points_per_line: 50.0
+        }
+    }
+}
+
+impl ScrollSettings {
+    /// The `Event::MouseWheel` of a GLFW scroll event.
+    ///
+    /// Positive offsets reveal the content above and to the left in GLFW and egui alike, so neither axis is flipped.
+    pub fn mouse_wheel(&self, x_offset: f64, y_offset: f64, modifiers: Modifiers) -> Event {
+        let offset = vec2(x_offset as f32, y_offset as f32);
+        let smooth = self.detect_smooth_scroll && (offset.x.fract() != 0.0 || offset.y.fract() != 0.0);
+        let (unit, delta) = if smooth {
+            (MouseWheelUnit::Point, offset * self.pixel_scale)
+        } else {
+            (MouseWheelUnit::Line, offset * self.line_scale)
+        };
+        Event::MouseWheel { unit, delta, modifiers }
+    }
+
+    // the fallback for egui 0.27: ctrl or command zooms and shift scrolls horizontally, as before MouseWheel
+    pub(crate) fn scroll_event(&self, unit: MouseWheelUnit, delta: Vec2, modifiers: Modifiers, screen_height: f32) -> Event {
+        let delta = match unit {
+            MouseWheelUnit::Point => delta,
+            MouseWheelUnit::Line => delta * self.points_per_line,
+            MouseWheelUnit::Page => delta * screen_height
+        };
+        if modifiers.ctrl || modifiers.command {
+            Event::Zoom((delta.y / 200.0).exp())
+        } else if modifiers.shift {
+            // on macOS the system already turns it into horizontal scrolling
+            Event::Scroll(vec2(delta.x + delta.y, 0.0))
+        } else {
+            Event::Scroll(delta)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use egui::{Event, Modifiers, MouseWheelUnit, vec2};
+
+    use super::ScrollSettings;
+
+    #[test]
+    fn wheels_scroll_lines_and_touchpads_points() {
+        let settings = ScrollSettings::default();
+        let cases = [
+            ((0.0, 1.0), MouseWheelUnit::Line, vec2(0.0, 1.0)),
+            ((-2.0, 0.0), MouseWheelUnit::Line, vec2(-2.0, 0.0)),
+            ((0.3, -1.5), MouseWheelUnit::Point, vec2(3.0, -15.0)),
+            ((1.0, 0.25), MouseWheelUnit::Point, vec2(10.0, 2.5))
+        ];
+        for ((x_offset, y_offset), unit, delta) in cases {
+            let event = settings.mouse_wheel(x_offset, y_offset, Modifiers::NONE);
+            assert_eq!(event, Event::MouseWheel { unit, delta, modifiers: Modifiers::NONE }, "{} {}", x_offset, y_offset);
+        }
+
+        let lines = ScrollSettings { detect_smooth_scroll: false, line_scale: 3.0, ..settings };
+        let event = lines.mouse_wheel(0.0, 0.5, Modifiers::NONE);
+        assert_eq!(event, Event::MouseWheel { unit: MouseWheelUnit::Line, delta: vec2(0.0, 1.5), modifiers: Modifiers::NONE });
+    }
+
+    #[test]
+    fn whole_touchpad_offsets_scroll_a_line() {
+        let settings = ScrollSettings::default();
+        let scroll = |y_offset| match settings.mouse_wheel(0.0, y_offset, Modifiers::NONE) {
+            Event::MouseWheel { unit, delta, modifiers } => settings.scroll_event(unit, delta, modifiers, 600.0),
+            event => panic!("{:?}", event)
+        };
+        // the touchpad speeds up from 9 to 50 points, a wheel notch gives the same offset
+        assert_eq!(scroll(0.9), Event::Scroll(vec2(0.0, 9.0)));
+        assert_eq!(scroll(1.0), Event::Scroll(vec2(0.0, 50.0)));
+    }
+
+    #[test]
+    fn scroll_events_follow_the_modifiers() {
+        let settings = ScrollSettings::default();
+        let scroll = |unit, delta, modifiers| settings.scroll_event(unit, delta, modifiers, 600.0);
+
+        assert_eq!(scroll(MouseWheelUnit::Line, vec2(1.0, -1.0), Modifiers::NONE), Event::Scroll(vec2(50.0, -50.0)));
+        assert_eq!(scroll(MouseWheelUnit::Point, vec2(3.0, 4.0), Modifiers::NONE), Event::Scroll(vec2(3.0, 4.0)));
+        assert_eq!(scroll(MouseWheelUnit::Page, vec2(0.0, 1.0), Modifiers::NONE), Event::Scroll(vec2(0.0, 600.0)));
+        assert_eq!(scroll(MouseWheelUnit::Line, vec2(0.0, 1.0), Modifiers::SHIFT), Event::Scroll(vec2(50.0, 0.0)));
+        assert_eq!(scroll(MouseWheelUnit::Line, vec2(0.0, 4.0), Modifiers::COMMAND), Event::Zoom(1.0f32.exp()));
+        assert_eq!(scroll(MouseWheelUnit::Line, vec2(0.0, -4.0), Modifiers::CTRL), Event::Zoom((-1.0f32).exp()));
+    }
+}
@@ -0,0 +1,179 @@
+use egui::Modifiers;
+use glfw::{Action, Key, Modifiers as Mod};
+
+/// The platform whose conventions a `ShortcutMap` follows, chosen at runtime so every preset can be used anywhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Platform {
+    Windows,
+    MacOs,
+    Linux
+}
+
+impl Platform {
+    pub fn current() -> Self {
+        if cfg!(target_os = "macos") {
+            Platform::MacOs
+        } else if cfg!(target_os = "windows") {
+            Platform::Windows
+        } else {
+            Platform::Linux
+        }
+    }
+}
+
+/// Clipboard commands sent to egui as their own events.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShortcutCommand {
+    Cut,
+    Copy,
+    Paste
+}
+
+/// A key with exactly these modifiers held, Caps Lock and Num Lock don't matter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Shortcut {
+    pub modifiers: Mod,
+    pub key: Key
+}
+
+impl Shortcut {
+    pub fn new(modifiers: Mod, key: Key) -> Self {
+        Shortcut { modifiers: modifiers & SHORTCUT_MODIFIERS, key }
+    }
+}
+
+const SHORTCUT_MODIFIERS: Mod = Mod::Shift.union(Mod::Control).union(Mod::Alt).union(Mod::Super);
+
+/// Which modifier is egui's command key and which keys trigger cut, copy and paste.
+///
+/// Keys are GLFW key codes, i.e. positions on a US keyboard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShortcutMap {
+    platform: Platform,
+    // Super on macOS, Control everywhere else
+    command_modifier: Mod,
+    bindings: Vec<(Shortcut, ShortcutCommand)>
+}
+
+impl ShortcutMap {
+    /// The usual shortcuts of `platform`, Windows also has Shift+Delete, Ctrl+Insert and Shift+Insert.
+    pub fn for_platform(platform: Platform) -> Self {
+        let command_modifier = if platform == Platform::MacOs { Mod::Super } else { Mod::Control };
+        let mut map = ShortcutMap { platform, command_modifier, bindings: Vec::new() };
+        map.bind(Shortcut::new(command_modifier, Key::X), ShortcutCommand::Cut);
+        map.bind(Shortcut::new(command_modifier, Key::C), ShortcutCommand::Copy);
+        map.bind(Shortcut::new(command_modifier, Key::V), ShortcutCommand::Paste);
+        if platform == Platform::Windows {
+            map.bind(Shortcut::new(Mod::Shift, Key::Delete), ShortcutCommand::Cut);
+            map.bind(Shortcut::new(Mod::Control, Key::Insert), ShortcutCommand::Copy);
+            map.bind(Shortcut::new(Mod::Shift, Key::Insert), ShortcutCommand::Paste);
+        }
+        map
+    }
+
+    pub fn platform(&self) -> Platform {
+        self.platform
+    }
+
+    pub fn command_modifier(&self) -> Mod {
+        self.command_modifier
+    }
+
+    /// Only changes what egui sees as `command`, the bindings stay as they are.
+    pub fn set_command_modifier(&mut self, command_modifier: Mod) {
+        self.command_modifier = command_modifier & SHORTCUT_MODIFIERS;
+    }
+
+    /// Replaces the command `shortcut` triggered before.
+    pub fn bind(&mut self, shortcut: Shortcut, command: ShortcutCommand) {
+        self.unbind(shortcut);
+        self.bindings.push((shortcut, command));
+    }
+
+    pub fn unbind(&mut self, shortcut: Shortcut) {
+        self.bindings.retain(|(bound, _)| *bound != shortcut);
+    }
+
+    pub fn bindings(&self) -> impl Iterator<Item = &(Shortcut, ShortcutCommand)> {
+        self.bindings.iter()
+    }
+
+    pub fn translate_modifiers(&self, keymod: Mod) -> Modifiers {
+        let mac = self.platform == Platform::MacOs;
+        Modifiers {
+            alt: keymod.contains(Mod::Alt),
+            ctrl: keymod.contains(Mod::Control),
+            shift: keymod.contains(Mod::Shift),
+            mac_cmd: mac && keymod.contains(Mod::Super),
+            command: keymod.intersects(self.command_modifier)
+        }
+    }
+
+    /// The command of a key press, held keys repeat it like text input does.
+    pub fn command(&self, keymod: Mod, key: Key, action: Action) -> Option<ShortcutCommand> {
+        if action == Action::Release {
+            return None;
+        }
+        let shortcut = Shortcut::new(keymod, key);
+        self.bindings.iter().find(|(bound, _)| *bound == shortcut).map(|(_, command)| *command)
+    }
+}
+
+impl Default for ShortcutMap {
+    fn default() -> Self {
+        Self::for_platform(Platform::current())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glfw::{Action, Key, Modifiers as Mod};
+
+    use super::{Platform, Shortcut, ShortcutCommand, ShortcutMap};
+
+    #[test]
+    fn presets_follow_the_platform() {
+        let cases = [
+            (Platform::Linux, Mod::Control, Key::C, Some(ShortcutCommand::Copy)),
+            (Platform::Linux, Mod::Control | Mod::CapsLock, Key::V, Some(ShortcutCommand::Paste)),
+            (Platform::Linux, Mod::Control | Mod::Shift, Key::C, None),
+            (Platform::Linux, Mod::Super, Key::C, None),
+            (Platform::Linux, Mod::Control, Key::Insert, None),
+            (Platform::Windows, Mod::Control, Key::X, Some(ShortcutCommand::Cut)),
+            (Platform::Windows, Mod::Shift, Key::Delete, Some(ShortcutCommand::Cut)),
+            (Platform::Windows, Mod::Control, Key::Insert, Some(ShortcutCommand::Copy)),
+            (Platform::Windows, Mod::Shift, Key::Insert, Some(ShortcutCommand::Paste)),
+            (Platform::MacOs, Mod::Super, Key::C, Some(ShortcutCommand::Copy)),
+            (Platform::MacOs, Mod::Control, Key::C, None),
+            (Platform::MacOs, Mod::Shift, Key::Insert, None)
+        ];
+        for (platform, keymod, key, command) in cases {
+            let map = ShortcutMap::for_platform(platform);
+            assert_eq!(map.command(keymod, key, Action::Press), command, "{:?} {:?} {:?}", platform, keymod, key);
+            assert_eq!(map.command(keymod, key, Action::Repeat), command, "{:?} {:?} {:?}", platform, keymod, key);
+            assert_eq!(map.command(keymod, key, Action::Release), None);
+        }
+    }
+
+    #[test]
+    fn command_is_super_on_macos() {
+        let mac = ShortcutMap::for_platform(Platform::MacOs).translate_modifiers(Mod::Super);
+        assert!(mac.command && mac.mac_cmd && !mac.ctrl);
+        let linux = ShortcutMap::for_platform(Platform::Linux).translate_modifiers(Mod::Super);
+        assert!(!linux.command && !linux.mac_cmd);
+        let windows = ShortcutMap::for_platform(Platform::Windows).translate_modifiers(Mod::Control | Mod::Shift);
+        assert!(windows.command && windows.ctrl && windows.shift && !windows.mac_cmd);
+    }
+
+    #[test]
+    fn custom_bindings_replace_and_remove_shortcuts() {
+        let mut map = ShortcutMap::for_platform(Platform::Linux);
+        map.bind(Shortcut::new(Mod::Control, Key::Insert), ShortcutCommand::Copy);
+        map.bind(Shortcut::new(Mod::Control, Key::C), ShortcutCommand::Cut);
+        map.unbind(Shortcut::new(Mod::Control, Key::V));
+        assert_eq!(map.command(Mod::Control, Key::Insert, Action::Press), Some(ShortcutCommand::Copy));
+        assert_eq!(map.command(Mod::Control, Key::C, Action::Press), Some(ShortcutCommand::Cut));
+        assert_eq!(map.command(Mod::Control, Key::V, Action::Press), None);
+        assert_eq!(map.bindings().count(), 3);
+    }
+}
@@ -1,7 +1,8 @@
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 use std::ffi::c_uint;
+use std::rc::{Rc, Weak};
 
-use egui::{TextureFilter, TextureOptions, TextureWrapMode};
+use egui::{TextureFilter, TextureId, TextureOptions, TextureWrapMode};
 use gl33::*;
 use gl33::global_loader::*;
 
@@ -11,20 +12,53 @@ pub struct GuiTexture {
     options: TextureOptions,
 
     size: [usize;2],
-    // always be [] if not custom texture
-    pixels: Vec<u8>,
-    // always be false if not custom texture
-    dirty: Cell<bool>
+    // uploads of a texture created with GuiRender::new_texture waiting for the next render, egui's own
+    // textures upload right away and native ones are never uploaded
+    pending: Vec<PendingUpload>,
+
+    // textures registered from outside are never deleted here, their options live in a sampler object
+    native: bool,
+    sampler_id: c_uint
+}
+
+// Pixels of a custom texture waiting for the next render, `pos` is None for the whole (maybe resized) image
+pub struct PendingUpload {
+    pub pos: Option<[usize;2]>,
+    pub size: [usize;2],
+    pub pixels: Vec<u8>
 }
 
 impl GuiTexture {
-    pub fn new(texture_id: c_uint, options: TextureOptions, size: [usize;2], pixels: Vec<u8>, dirty: bool) -> Self {
+    pub fn new(texture_id: c_uint, options: TextureOptions, size: [usize;2]) -> Self {
+        Self {
+            texture_id: Cell::new(texture_id),
+            options,
+            size,
+            pending: vec![],
+            native: false,
+            sampler_id: 0
+        }
+    }
+
+    pub fn new_native(texture_id: c_uint, options: TextureOptions, size: [usize;2]) -> Self {
+        assert_ne!(texture_id, 0);
+        let mut sampler_id = 0;
+        unsafe {
+            glGenSamplers(1, &mut sampler_id);
+            assert_ne!(sampler_id, 0);
+            let wrap_mode = gl_wrap_mode(&options);
+            glSamplerParameteri(sampler_id, GL_TEXTURE_WRAP_S, wrap_mode.0 as _);
+            glSamplerParameteri(sampler_id, GL_TEXTURE_WRAP_T, wrap_mode.0 as _);
+            glSamplerParameteri(sampler_id, GL_TEXTURE_MIN_FILTER, gl_filter(&options.minification).0 as _);
+            glSamplerParameteri(sampler_id, GL_TEXTURE_MAG_FILTER, gl_filter(&options.magnification).0 as _);
+        }
         Self {
             texture_id: Cell::new(texture_id),
             options,
             size,
-            pixels,
-            dirty: Cell::new(dirty)
+            pending: vec![],
+            native: true,
+            sampler_id
         }
     }
 
@@ -34,20 +68,123 @@ impl GuiTexture {
     pub fn size(&self) -> [usize; 2] {
         self.size
     }
+    pub fn options(&self) -> TextureOptions {
+        self.options
+    }
+    pub fn is_native(&self) -> bool {
+        self.native
+    }
+    pub fn sampler_id(&self) -> c_uint {
+        self.sampler_id
+    }
     pub fn dirty(&self) -> bool {
-        self.dirty.get()
+        !self.pending.is_empty()
     }
-    pub fn take_pixels(&mut self) -> Vec<u8> {
-        std::mem::take(&mut self.pixels)
+    pub fn take_pending(&mut self) -> Vec<PendingUpload> {
+        std::mem::take(&mut self.pending)
     }
     pub fn set_texture_id(&self, texture_id: c_uint) {
         self.texture_id.set(texture_id);
     }
-    pub fn set_pixels(&mut self, pixels: Vec<u8>) {
-        self.pixels = pixels;
+    pub fn set_pixels(&mut self, size: [usize;2], pixels: Vec<u8>) {
+        // a whole image makes every older upload obsolete
+        self.size = size;
+        self.pending.clear();
+        self.pending.push(PendingUpload { pos: None, size, pixels });
+    }
+    pub fn set_sub_pixels(&mut self, pos: [usize;2], size: [usize;2], pixels: Vec<u8>) {
+        self.pending.push(PendingUpload { pos: Some(pos), size, pixels });
+    }
+}
+
+/// Handle of a texture created with `GuiRender::new_texture`, owned by the `GuiRender`.
+///
+/// The gl texture is freed by `GuiRender::free_user_texture`, or during the next render after the handle is dropped,
+/// at the latest when the `GuiRender` is dropped.
+pub struct UserTexture {
+    id: TextureId,
+    // gone once the GuiRender is dropped
+    free_queue: Weak<RefCell<Vec<DroppedTexture>>>,
+    // deletes gl objects of the owner once GuiRender no longer samples them
+    release: Option<Box<dyn FnOnce()>>
+}
+
+// A dropped handle waiting for GuiRender to unregister its texture
+pub(crate) struct DroppedTexture {
+    pub id: TextureId,
+    pub release: Option<Box<dyn FnOnce()>>
+}
+
+impl UserTexture {
+    pub(crate) fn new(id: TextureId, free_queue: Weak<RefCell<Vec<DroppedTexture>>>) -> Self {
+        Self {
+            id,
+            free_queue,
+            release: None
+        }
+    }
+
+    pub fn id(&self) -> TextureId {
+        self.id
+    }
+
+    // Runs `release` after the texture is unregistered, e.g. to delete a native texture only then
+    pub(crate) fn release_with(&mut self, release: impl FnOnce() + 'static) {
+        self.release = Some(Box::new(release));
+    }
+}
+
+impl Drop for UserTexture {
+    fn drop(&mut self) {
+        let release = self.release.take();
+        match self.free_queue.upgrade() {
+            Some(free_queue) => free_queue.borrow_mut().push(DroppedTexture { id: self.id, release }),
+            // nothing samples the texture anymore
+            None => {
+                if let Some(release) = release {
+                    release();
+                }
+            }
+        }
+    }
+}
+
+// Hands out the ids of user textures and collects the handles dropped since the last render.
+// Ids count up and are never reused, egui may still reference a freed one for a frame.
+#[derive(Default)]
+pub(crate) struct UserTextureIds {
+    next_id: u64,
+    free_queue: Rc<RefCell<Vec<DroppedTexture>>>
+}
+
+impl UserTextureIds {
+    pub fn next_id(&mut self) -> TextureId {
+        let id = TextureId::User(self.next_id);
+        self.next_id += 1;
+        id
+    }
+
+    pub fn next_handle(&mut self) -> UserTexture {
+        let id = self.next_id();
+        UserTexture::new(id, Rc::downgrade(&self.free_queue))
+    }
+
+    // Calls `free` with the id of every dropped handle, then runs its release
+    pub fn free_dropped(&self, mut free: impl FnMut(TextureId)) {
+        let dropped = std::mem::take(&mut *self.free_queue.borrow_mut());
+        for DroppedTexture { id, release } in dropped {
+            free(id);
+            if let Some(release) = release {
+                release();
+            }
+        }
     }
-    pub fn set_dirty(&self, dirty: bool) {
-        self.dirty.set(dirty);
+}
+
+impl Drop for UserTextureIds {
+    // releases what was dropped since the last render, handles dropped later release right away
+    fn drop(&mut self) {
+        self.free_dropped(|_| {});
     }
 }
 
@@ -62,37 +199,32 @@ impl GuiTexture {
             // bind
             glBindTexture(GL_TEXTURE_2D, texture_id);
             // settings
-            let warap_mod = match &self.options.wrap_mode {
-                TextureWrapMode::ClampToEdge => GL_CLAMP_TO_EDGE,
-                TextureWrapMode::Repeat => GL_REPEAT,
-                TextureWrapMode::MirroredRepeat => GL_MIRRORED_REPEAT
-            };
-            glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_S, warap_mod.0 as _);
-            glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_T, warap_mod.0 as _);
-            glTexParameteri(GL_TEXTURE_2D,
-                            GL_TEXTURE_MIN_FILTER,
-                            match &self.options.minification {
-                                TextureFilter::Nearest => GL_NEAREST,
-                                TextureFilter::Linear => GL_LINEAR
-                            }.0 as _
-            );
-            glTexParameteri(GL_TEXTURE_2D,
-                            GL_TEXTURE_MAG_FILTER,
-                            match &self.options.magnification {
-                                TextureFilter::Nearest => GL_NEAREST,
-                                TextureFilter::Linear => GL_LINEAR
-                            }.0 as _
-            );
+            let wrap_mode = gl_wrap_mode(&self.options);
+            glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_S, wrap_mode.0 as _);
+            glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_T, wrap_mode.0 as _);
+            glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MIN_FILTER, gl_filter(&self.options.minification).0 as _);
+            glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MAG_FILTER, gl_filter(&self.options.magnification).0 as _);
         }
     }
 
     pub fn free(&self) {
-        let texture = self.texture_id();
-        if texture != 0 {
+        if self.native {
             unsafe {
-                glDeleteTextures(1, texture as _);
+                glDeleteSamplers(1, &self.sampler_id);
             }
+            return;
         }
+        if self.owns_texture() {
+            unsafe {
+                glDeleteTextures(1, &self.texture_id());
+            }
+            self.set_texture_id(0);
+        }
+    }
+
+    // the gl texture of a native texture belongs to the caller, free() only deletes textures created here
+    fn owns_texture(&self) -> bool {
+        !self.native && self.texture_id() != 0
     }
 }
 
@@ -101,6 +233,7 @@ impl GuiTexture {
         let texture_id = self.texture_id();
         assert_ne!(texture_id, 0);
         let [texture_width, texture_height] = self.size;
+        assert_eq!(texture_width * texture_height * 4, data.len());
         unsafe {
             glBindTexture(GL_TEXTURE_2D, texture_id);
             glTexImage2D(
@@ -138,3 +271,101 @@ impl GuiTexture {
         }
     }
 }
+
+fn gl_wrap_mode(options: &TextureOptions) -> GLenum {
+    match &options.wrap_mode {
+        TextureWrapMode::ClampToEdge => GL_CLAMP_TO_EDGE,
+        TextureWrapMode::Repeat => GL_REPEAT,
+        TextureWrapMode::MirroredRepeat => GL_MIRRORED_REPEAT
+    }
+}
+
+fn gl_filter(filter: &TextureFilter) -> GLenum {
+    match filter {
+        TextureFilter::Nearest => GL_NEAREST,
+        TextureFilter::Linear => GL_LINEAR
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::collections::HashSet;
+    use std::rc::Rc;
+
+    use egui::{TextureId, TextureOptions};
+
+    use super::{GuiTexture, UserTextureIds};
+
+    #[test]
+    fn only_textures_created_here_are_deleted() {
+        let native = GuiTexture { native: true, sampler_id: 3, ..GuiTexture::new(7, TextureOptions::LINEAR, [1, 1]) };
+        assert!(!native.owns_texture());
+        assert!(GuiTexture::new(7, TextureOptions::LINEAR, [1, 1]).owns_texture());
+        // not uploaded yet
+        assert!(!GuiTexture::new(0, TextureOptions::LINEAR, [1, 1]).owns_texture());
+    }
+
+    #[test]
+    fn ids_are_unique_and_never_reused() {
+        let mut ids = UserTextureIds::default();
+        let first = ids.next_handle();
+        let second = ids.next_handle();
+        let native = ids.next_id();
+        let first_id = first.id();
+        drop(first);
+        ids.free_dropped(|_| {});
+        let third = ids.next_handle();
+
+        let allocated = [first_id, second.id(), native, third.id()];
+        assert_eq!(allocated.iter().collect::<HashSet<_>>().len(), allocated.len());
+        // egui's own textures are Managed, user ids can't collide with them
+        assert!(allocated.iter().all(|id| matches!(id, TextureId::User(_))));
+        assert_eq!(allocated, [TextureId::User(0), TextureId::User(1), TextureId::User(2), TextureId::User(3)]);
+    }
+
+    #[test]
+    fn handles_are_released_when_their_owner_is_gone() {
+        let released = Rc::new(Cell::new(0));
+        let release = |released: &Rc<Cell<i32>>| {
+            let counter = released.clone();
+            move || counter.set(counter.get() + 1)
+        };
+        let mut ids = UserTextureIds::default();
+        let mut queued = ids.next_handle();
+        queued.release_with(release(&released));
+        let mut alive = ids.next_handle();
+        alive.release_with(release(&released));
+
+        drop(queued);
+        drop(ids);
+        assert_eq!(released.get(), 1);
+        drop(alive);
+        assert_eq!(released.get(), 2);
+    }
+
+    #[test]
+    fn dropped_handles_are_freed_and_released_once() {
+        let mut ids = UserTextureIds::default();
+        let released = Rc::new(Cell::new(0));
+        let mut first = ids.next_handle();
+        let counter = released.clone();
+        first.release_with(move || counter.set(counter.get() + 1));
+        let second = ids.next_handle();
+        let (first_id, second_id) = (first.id(), second.id());
+
+        let mut freed = Vec::new();
+        ids.free_dropped(|id| freed.push(id));
+        assert!(freed.is_empty());
+
+        drop(first);
+        drop(second);
+        ids.free_dropped(|id| freed.push(id));
+        assert_eq!(freed, [first_id, second_id]);
+        assert_eq!(released.get(), 1);
+
+        ids.free_dropped(|id| freed.push(id));
+        assert_eq!(freed.len(), 2);
+        assert_eq!(released.get(), 1);
+    }
+}
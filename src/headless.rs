@@ -0,0 +1,144 @@
+use std::ffi::c_uint;
+
+use egui::Rgba;
+use gl33::*;
+use gl33::global_loader::*;
+use glfw::{ContextCreationApi, Glfw, GlfwReceiver, PWindow, WindowEvent};
+
+use crate::glfw_window::create_gl_window;
+use crate::gui::GuiContext;
+
+/// Renders egui frames into an offscreen framebuffer of a never shown GLFW window.
+///
+/// With a software driver like Mesa llvmpipe this works on machines without a GPU. GLFW 3.3 still needs an
+/// X11 or Wayland display to initialize, also with `ContextCreationApi::OsMesa` or `ContextCreationApi::Egl`,
+/// so CI runs it under Xvfb (`xvfb-run`).
+pub struct HeadlessWindow {
+    width: u32,
+    height: u32,
+    pixels_per_point: f32,
+    clear_color: Rgba,
+
+    fbo_id: c_uint,
+    color_texture_id: c_uint,
+
+    pub gui_ctx: GuiContext,
+
+    // keep the context alive, declared last so it is dropped after all gl resources
+    _events: GlfwReceiver<(f64, WindowEvent)>,
+    _window: PWindow,
+    _glfw: Glfw,
+}
+
+impl HeadlessWindow {
+    pub fn new(width: u32, height: u32, pixels_per_point: f32) -> Self {
+        Self::with_context_api(width, height, pixels_per_point, ContextCreationApi::Native)
+    }
+
+    pub fn with_context_api(width: u32, height: u32, pixels_per_point: f32, context_api: ContextCreationApi) -> Self {
+        let (glfw, window, events) = create_gl_window(width, height, "headless", context_api);
+        unsafe {
+            // color target, same format as the srgb capable default framebuffer
+            let mut color_texture_id = 0;
+            glGenTextures(1, &mut color_texture_id);
+            assert_ne!(color_texture_id, 0);
+            glBindTexture(GL_TEXTURE_2D, color_texture_id);
+            glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MIN_FILTER, GL_NEAREST.0 as _);
+            glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MAG_FILTER, GL_NEAREST.0 as _);
+            glTexImage2D(
+                GL_TEXTURE_2D,
+                0,
+                GL_SRGB8_ALPHA8.0 as _,
+                width as _,
+                height as _,
+                0,
+                GL_RGBA,
+                GL_UNSIGNED_BYTE,
+                core::ptr::null()
+            );
+            glBindTexture(GL_TEXTURE_2D, 0);
+
+            let mut fbo_id = 0;
+            glGenFramebuffers(1, &mut fbo_id);
+            assert_ne!(fbo_id, 0);
+            glBindFramebuffer(GL_FRAMEBUFFER, fbo_id);
+            glFramebufferTexture2D(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT0, GL_TEXTURE_2D, color_texture_id, 0);
+            assert_eq!(glCheckFramebufferStatus(GL_FRAMEBUFFER), GL_FRAMEBUFFER_COMPLETE, "Error: offscreen framebuffer is incomplete");
+            glBindFramebuffer(GL_FRAMEBUFFER, 0);
+
+            HeadlessWindow {
+                width,
+                height,
+                pixels_per_point,
+                clear_color: Rgba::BLACK,
+
+                fbo_id,
+                color_texture_id,
+
+                gui_ctx: GuiContext::from_size(width as usize, height as usize, pixels_per_point),
+
+                _events: events,
+                _window: window,
+                _glfw: glfw,
+            }
+        }
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    pub fn set_clear_color(&mut self, color: Rgba) {
+        self.clear_color = color;
+    }
+}
+
+impl HeadlessWindow {
+    /// Runs one egui frame and returns the result as tightly packed RGBA rows, top row first.
+    pub fn render(&mut self, elapsed_time: f64, add_contents: impl FnOnce(&mut GuiContext)) -> Vec<u8> {
+        self.gui_ctx.start(elapsed_time);
+        add_contents(&mut self.gui_ctx);
+        let egui_output = self.gui_ctx.egui_ctx.end_frame();
+
+        unsafe {
+            glBindFramebuffer(GL_FRAMEBUFFER, self.fbo_id);
+            glViewport(0, 0, self.width as _, self.height as _);
+            let color = self.clear_color;
+            glClearColor(color.r(), color.g(), color.b(), color.a());
+            glClear(GL_COLOR_BUFFER_BIT);
+        }
+        self.gui_ctx.render(egui_output, self.pixels_per_point);
+        let pixels = self.read_pixels();
+        unsafe {
+            glBindFramebuffer(GL_FRAMEBUFFER, 0);
+        }
+        pixels
+    }
+
+    pub fn read_pixels(&self) -> Vec<u8> {
+        let (width, height) = (self.width as usize, self.height as usize);
+        let mut pixels = vec![0u8; width * height * 4];
+        unsafe {
+            glBindFramebuffer(GL_READ_FRAMEBUFFER, self.fbo_id);
+            glPixelStorei(GL_PACK_ALIGNMENT, 1);
+            glReadPixels(0, 0, width as _, height as _, GL_RGBA, GL_UNSIGNED_BYTE, pixels.as_mut_ptr().cast());
+            glBindFramebuffer(GL_READ_FRAMEBUFFER, 0);
+        }
+        // gl rows start at the bottom
+        let row_len = width * 4;
+        let mut flipped = Vec::with_capacity(pixels.len());
+        for row in pixels.chunks_exact(row_len).rev() {
+            flipped.extend_from_slice(row);
+        }
+        flipped
+    }
+}
+
+impl Drop for HeadlessWindow {
+    fn drop(&mut self) {
+        unsafe {
+            glDeleteFramebuffers(1, &self.fbo_id);
+            glDeleteTextures(1, &self.color_texture_id);
+        }
+    }
+}
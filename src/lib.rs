@@ -2,4 +2,5 @@ pub mod gui;
 pub mod shader;
 pub mod timer;
 pub mod glfw_window;
+pub mod headless;
 mod triangle;
@@ -0,0 +1,519 @@
+use std::cell::{Cell, Ref, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::os::raw::{c_float, c_int, c_uint};
+use std::rc::Rc;
+use std::time::SystemTime;
+
+use cgmath::Matrix4;
+use gl33::*;
+use gl33::global_loader::*;
+
+pub use self::shader_compute::*;
+pub use self::shader_error::*;
+pub use self::shader_extension::*;
+pub use self::shader_parser::*;
+pub use self::shader_preprocessor::*;
+pub use self::shader_program_cache::*;
+pub use self::shader_reflection::*;
+pub use self::shader_uniform::*;
+pub use self::shader_uniform_buffer::*;
+
+mod shader_compute;
+mod shader_error;
+mod shader_extension;
+mod shader_parser;
+mod shader_preprocessor;
+mod shader_program_cache;
+mod shader_reflection;
+mod shader_uniform;
+mod shader_uniform_buffer;
+
+/// Shaders registered by name, compiled the first time they are requested.
+///
+/// Handles stay valid after the set is dropped, a program is deleted with the last `Rc<Shader>`.
+#[derive(Default)]
+pub struct ShaderSet {
+    entries: RefCell<HashMap<String, ShaderEntry>>
+}
+
+struct ShaderEntry {
+    origin: ShaderOrigin,
+    // None until the first successful `get`
+    shader: Option<Rc<Shader>>
+}
+
+enum ShaderOrigin {
+    File {
+        file_path: String,
+        defines: Vec<(String, String)>
+    },
+    Embedded {
+        source: String,
+        includes: Vec<(String, String)>
+    }
+}
+
+impl ShaderSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_file(&mut self, name: &str, file_path: &str) {
+        self.add_file_with_defines(name, file_path, &[]);
+    }
+
+    // `defines` are added after the #version of every stage, an empty value defines just the name
+    pub fn add_file_with_defines(&mut self, name: &str, file_path: &str, defines: &[(&str, &str)]) {
+        self.add(name, ShaderOrigin::File { file_path: file_path.to_string(), defines: to_owned_defines(defines) });
+    }
+
+    /// Adds a shader compiled into the binary, `includes` holds the (path, source) of every file it includes.
+    pub fn add_source(&mut self, name: &str, source: &str, includes: &[(&str, &str)]) {
+        let includes = includes.iter().map(|(path, source)| (path.to_string(), source.to_string())).collect();
+        self.add(name, ShaderOrigin::Embedded { source: source.to_string(), includes });
+    }
+
+    // replaces a shader of the same name, handles to the old one keep working
+    fn add(&mut self, name: &str, origin: ShaderOrigin) {
+        self.entries.get_mut().insert(name.to_string(), ShaderEntry { origin, shader: None });
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.entries.get_mut().remove(name);
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.borrow().contains_key(name)
+    }
+
+    /// Compiles the shader on first use. A shader that fails is tried again by the next call.
+    pub fn get(&self, name: &str) -> Result<Rc<Shader>, ShaderError> {
+        let mut entries = self.entries.borrow_mut();
+        let entry = entries.get_mut(name).ok_or_else(|| ShaderError::Unknown { name: name.to_string() })?;
+        if let Some(shader) = &entry.shader {
+            return Ok(Rc::clone(shader));
+        }
+        let shader = Rc::new(match &entry.origin {
+            ShaderOrigin::File { file_path, defines } => Shader::build_file(file_path, defines.clone())?,
+            ShaderOrigin::Embedded { source, includes } => {
+                let includes: Vec<(&str, &str)> = includes.iter().map(|(path, source)| (path.as_str(), source.as_str())).collect();
+                Shader::from_embedded_source(name, source, &includes)?
+            }
+        });
+        entry.shader = Some(Rc::clone(&shader));
+        Ok(shader)
+    }
+
+    /// Compiles every shader that wasn't requested yet, e.g. behind a loading screen.
+    pub fn load_all(&self) -> Vec<(String, ShaderError)> {
+        let names: Vec<String> = self.entries.borrow().keys().cloned().collect();
+        names.into_iter().filter_map(|name| self.get(&name).err().map(|err| (name, err))).collect()
+    }
+
+    /// Reloads every compiled shader, the ones that fail keep their old program.
+    ///
+    /// Shaders added with `add_source` have no file and are reported as `ShaderError::NotReloadable`.
+    pub fn reload_all(&self) -> Vec<(String, ShaderError)> {
+        self.for_each_loaded(|shader| shader.reload())
+    }
+
+    /// Reloads the compiled shaders whose files changed since they were last loaded.
+    pub fn reload_modified(&self) -> Vec<(String, ShaderError)> {
+        self.for_each_loaded(|shader| shader.reload_if_modified().map(|_| ()))
+    }
+
+    fn for_each_loaded(&self, f: impl Fn(&Shader) -> Result<(), ShaderError>) -> Vec<(String, ShaderError)> {
+        self.entries
+            .borrow()
+            .iter()
+            .filter_map(|(name, entry)| entry.shader.as_ref().map(|shader| (name, shader)))
+            .filter_map(|(name, shader)| f(shader).err().map(|err| (name.clone(), err)))
+            .collect()
+    }
+}
+
+/// A linked program, deleted when dropped. Share it as `Rc<Shader>`.
+#[derive(Debug)]
+pub struct Shader {
+    // replaced by a successful reload
+    shader_program_id: Cell<c_uint>,
+
+    file_path: String,
+    defines: Vec<(String, String)>,
+    // every stage of the program as it was compiled
+    sources: RefCell<Vec<(ShaderStage, PreprocessedSource)>>,
+    reflection: RefCell<ShaderReflection>,
+    // uniform names already warned about
+    warned_uniforms: RefCell<HashSet<String>>,
+
+    // shaders built from a string have nothing to reload
+    from_file: bool,
+    // the shader file and its includes with their mtime when they were last (re)loaded
+    watched_files: RefCell<Vec<(String, Option<SystemTime>)>>,
+}
+
+impl Shader {
+    pub fn new(file_path: &str) -> Result<Self, ShaderError> {
+        Self::with_defines(file_path, &[])
+    }
+
+    // `defines` are added after the #version of every stage, an empty value defines just the name
+    pub fn with_defines(file_path: &str, defines: &[(&str, &str)]) -> Result<Self, ShaderError> {
+        Self::build_file(file_path, to_owned_defines(defines))
+    }
+
+    // `name` takes the place of the file path, includes are read from files relative to it
+    pub fn from_source(name: &str, source: &str) -> Result<Self, ShaderError> {
+        Self::build(name, source, Vec::new(), &read_file)
+    }
+
+    fn build_file(file_path: &str, defines: Vec<(String, String)>) -> Result<Self, ShaderError> {
+        let modified = file_modified(file_path);
+        let source = load_shader(file_path)?;
+        let mut shader = Self::build(file_path, &source, defines, &read_file)?;
+        shader.from_file = true;
+        shader.watched_files = RefCell::new(watch_files(&shader.sources.borrow()));
+        // the source read above may already be older than a change made while compiling
+        shader.watched_files.borrow_mut()[0].1 = modified;
+        Ok(shader)
+    }
+
+    // for shaders compiled into the binary, `name` takes the place of the file path
+    // and `includes` holds the (path, source) of every file they include
+    pub fn from_embedded_source(name: &str, source: &str, includes: &[(&str, &str)]) -> Result<Self, ShaderError> {
+        let read_include = |path: &str| includes
+            .iter()
+            .find(|(include_path, _)| *include_path == path)
+            .map(|(_, source)| source.to_string())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "not an embedded shader file"));
+        Self::build(name, source, Vec::new(), &read_include)
+    }
+
+    fn build(
+        file_path: &str,
+        source: &str,
+        defines: Vec<(String, String)>,
+        read_file: &dyn Fn(&str) -> io::Result<String>
+    ) -> Result<Self, ShaderError> {
+        let sources = preprocess_stages(file_path, source, &defines, read_file)?;
+        let program_id = build_program(file_path, &sources)?;
+        let reflection = ShaderReflection::query(program_id);
+        bind_uniform_blocks(program_id, &reflection);
+        Ok(Self {
+            shader_program_id: Cell::new(program_id),
+            file_path: file_path.to_string(),
+            defines,
+            sources: RefCell::new(sources),
+            reflection: RefCell::new(reflection),
+            warned_uniforms: RefCell::new(HashSet::new()),
+            from_file: false,
+            watched_files: RefCell::new(Vec::new())
+        })
+    }
+
+    pub fn file_path(&self) -> &str {
+        &self.file_path
+    }
+
+    /// True if the shader file or one of its includes changed on disk since it was last (re)loaded.
+    /// False for shaders built from a source string, `reload` fails with `ShaderError::NotReloadable` for them.
+    pub fn is_reloadable(&self) -> bool {
+        self.from_file
+    }
+
+    pub fn is_modified(&self) -> bool {
+        self.watched_files
+            .borrow()
+            .iter()
+            .any(|(file_path, modified)| file_modified(file_path) != *modified)
+    }
+
+    /// Reads and compiles the shader file again. The new program is only swapped in if it compiles and links,
+    /// otherwise the old one stays in use and the error carries the compile log. Shaders built from a source
+    /// string fail with `ShaderError::NotReloadable`.
+    pub fn reload(&self) -> Result<(), ShaderError> {
+        if !self.from_file {
+            return Err(ShaderError::NotReloadable { file_path: self.file_path.clone() });
+        }
+        // remembered before compiling, a broken file is reported once instead of on every poll
+        for (file_path, modified) in self.watched_files.borrow_mut().iter_mut() {
+            *modified = file_modified(file_path);
+        }
+        let source = load_shader(&self.file_path)?;
+        let sources = preprocess_stages(&self.file_path, &source, &self.defines, &read_file)?;
+        let program_id = build_program(&self.file_path, &sources)?;
+
+        glDeleteProgram(self.shader_program_id.replace(program_id));
+        let reflection = ShaderReflection::query(program_id);
+        bind_uniform_blocks(program_id, &reflection);
+        *self.reflection.borrow_mut() = reflection;
+        self.warned_uniforms.borrow_mut().clear();
+        // includes may have been added or removed
+        *self.watched_files.borrow_mut() = watch_files(&sources);
+        *self.sources.borrow_mut() = sources;
+        Ok(())
+    }
+
+    /// Reloads the shader if its file changed, returns whether a new program was swapped in.
+    /// Cheap enough to be polled once per frame.
+    pub fn reload_if_modified(&self) -> Result<bool, ShaderError> {
+        if !self.is_modified() {
+            return Ok(false);
+        }
+        self.reload().map(|_| true)
+    }
+
+    pub fn is_compute(&self) -> bool {
+        self.sources.borrow().iter().any(|(stage, _)| *stage == ShaderStage::Compute)
+    }
+
+    /// Runs a compute shader with `groups` work groups in x, y and z, leaves the program attached.
+    pub fn dispatch_compute(&self, groups: [u32; 3]) {
+        assert!(self.is_compute(), "Error: {} is not a compute shader", self.file_path);
+        self.attach();
+        dispatch_compute(groups);
+    }
+
+    pub fn attach(&self) {
+        glUseProgram(self.shader_program_id.get());
+    }
+
+    pub fn detach(&self) {
+        glUseProgram(0);
+    }
+
+    /// Active uniforms and attributes of the current program.
+    pub fn reflection(&self) -> Ref<'_, ShaderReflection> {
+        self.reflection.borrow()
+    }
+
+    // -1 for names that are not active uniforms, gl ignores uploads to it
+    pub fn get_uniform_location(&self, name: &str) -> c_int {
+        match self.reflection.borrow().uniform_element(name) {
+            Some((uniform, index)) => uniform.element_locations[index],
+            None => {
+                self.warn_once(name, || format!("'{}' is not an active uniform", name));
+                -1
+            }
+        }
+    }
+
+    /// Uploads `value` to the uniform `name`, the shader has to be attached.
+    ///
+    /// `name` may be a uniform array element like `lights[2]`, arrays and slices fill the elements from there on.
+    /// Debug builds warn once if `value` doesn't fit the GLSL type or the array size.
+    pub fn set<U: Uniform>(&self, name: &str, value: U) {
+        let reflection = self.reflection.borrow();
+        let Some((uniform, index)) = reflection.uniform_element(name) else {
+            self.warn_once(name, || format!("'{}' is not an active uniform", name));
+            return;
+        };
+        let available = uniform.size - index;
+        if cfg!(debug_assertions) {
+            if !value.accepts(uniform.gl_type) {
+                self.warn_once(name, || format!("uniform '{}' is a {}, not a {}", name, uniform.type_name(), std::any::type_name::<U>()));
+            } else if value.element_count() > available {
+                self.warn_once(name, || format!("uniform '{}' has room for {} elements, got {}", name, available, value.element_count()));
+            }
+        }
+        unsafe {
+            value.upload(uniform.element_locations[index], value.element_count().min(available));
+        }
+    }
+
+    pub fn upload_mat4f(&self, name: &str, mat: Matrix4<f32>) {
+        self.set(name, mat);
+    }
+
+    pub fn upload_int_array(&self, name: &str, values: Vec<u32>) {
+        // int uniforms take i32 data
+        let values: Vec<i32> = values.iter().map(|value| *value as i32).collect();
+        self.set(name, &values);
+    }
+
+    fn warn_once(&self, name: &str, message: impl FnOnce() -> String) {
+        if self.warned_uniforms.borrow_mut().insert(name.to_string()) {
+            eprintln!("Warning: {}: {}", self.file_path, message());
+        }
+    }
+
+    pub fn upload_int(&self, location: c_int, value: usize) {
+        unsafe {
+            glUniform1i(location, value as c_int);
+        }
+    }
+
+    pub fn upload_float(&self, location: c_int, value: f32) {
+        unsafe {
+            glUniform1f(location, value as c_float);
+        }
+    }
+}
+
+impl Drop for Shader {
+    fn drop(&mut self) {
+        glDeleteProgram(self.shader_program_id.get());
+    }
+}
+
+impl PartialEq for Shader {
+    fn eq(&self, other: &Self) -> bool {
+        self.file_path.eq(&other.file_path)
+    }
+}
+
+/// Reads a shader file and resolves its includes and defines without touching OpenGL, e.g. to check it offline.
+pub fn load_shader_stages(file_path: &str, defines: &[(&str, &str)]) -> Result<Vec<(ShaderStage, PreprocessedSource)>, ShaderError> {
+    let source = load_shader(file_path)?;
+    preprocess_stages(file_path, &source, &to_owned_defines(defines), &read_file)
+}
+
+// the stages of a parsed shader file, ready to compile
+fn preprocess_stages(
+    file_path: &str,
+    source: &str,
+    defines: &[(String, String)],
+    read_file: &dyn Fn(&str) -> io::Result<String>
+) -> Result<Vec<(ShaderStage, PreprocessedSource)>, ShaderError> {
+    let sections = parse_shader(file_path, source)?;
+    // the parser keeps compute sections alone, everything else needs a vertex and a fragment stage
+    let is_compute = sections.iter().any(|section| section.stage == ShaderStage::Compute);
+    if !is_compute {
+        for stage in [ShaderStage::Vertex, ShaderStage::Fragment] {
+            if !sections.iter().any(|section| section.stage == stage) {
+                return Err(ShaderError::MissingStage { file_path: file_path.to_string(), stage });
+            }
+        }
+    }
+    sections
+        .iter()
+        .map(|section| Ok((section.stage, preprocess(file_path, section, defines, read_file)?)))
+        .collect()
+}
+
+fn build_program(file_path: &str, sources: &[(ShaderStage, PreprocessedSource)]) -> Result<c_uint, ShaderError> {
+    let is_compute = sources.iter().any(|(stage, _)| *stage == ShaderStage::Compute);
+    if is_compute && !compute_supported() {
+        return Err(ShaderError::UnsupportedStage { file_path: file_path.to_string(), stage: ShaderStage::Compute });
+    }
+    let cached = cached_program(sources);
+    if let Some(program_id) = cached.as_ref().and_then(CachedProgram::load) {
+        return Ok(program_id);
+    }
+    unsafe {
+        let mut shader_ids: Vec<c_uint> = Vec::new();
+        for (stage, source) in sources {
+            match compile_stage(file_path, *stage, source) {
+                Ok(shader_id) => shader_ids.push(shader_id),
+                Err(err) => {
+                    shader_ids.iter().for_each(|shader_id| glDeleteShader(*shader_id));
+                    return Err(err);
+                }
+            }
+        }
+
+        // Create an empty program
+        let program_id = glCreateProgram();
+        assert_ne!(program_id, 0);
+        // Attach the shaders of all stages to the program
+        for shader_id in &shader_ids {
+            glAttachShader(program_id, *shader_id);
+        }
+        if let Some(cached) = &cached {
+            cached.prepare(program_id);
+        }
+        // Link the program
+        glLinkProgram(program_id);
+        let result = check_link_result(program_id, file_path);
+
+        shader_ids.iter().for_each(|shader_id| glDeleteShader(*shader_id));
+        if let Err(err) = result {
+            glDeleteProgram(program_id);
+            return Err(err);
+        }
+        if let Some(cached) = &cached {
+            cached.store(program_id);
+        }
+        Ok(program_id)
+    }
+}
+
+unsafe fn compile_stage(file_path: &str, stage: ShaderStage, source: &PreprocessedSource) -> Result<c_uint, ShaderError> {
+    let shader_id = glCreateShader(stage.gl_type());
+    assert_ne!(shader_id, 0);
+    let source_text = &source.source;
+    // upload the shader source to the GPU
+    glShaderSource(
+        shader_id,                               // shader id
+        1,                                       // number of shaders
+        &source_text.as_bytes().as_ptr().cast(), // the shader source
+        &(source_text.len().try_into().unwrap()) // the length of the source
+    );
+    glCompileShader(shader_id);
+
+    let mut success = 0;
+    glGetShaderiv(shader_id, GL_COMPILE_STATUS, &mut success);
+    if success == 0 {
+        let mut log_len = 0;
+        glGetShaderiv(shader_id, GL_INFO_LOG_LENGTH, &mut log_len);
+        let mut log: Vec<u8> = vec![0; log_len.max(1) as usize];
+        glGetShaderInfoLog(shader_id, log.len() as _, &mut log_len, log.as_mut_ptr().cast());
+        log.truncate(log_len.max(0) as usize);
+        glDeleteShader(shader_id);
+        return Err(ShaderError::Compile {
+            file_path: file_path.to_string(),
+            stage,
+            log: source.map_log(&String::from_utf8_lossy(&log))
+        });
+    }
+    Ok(shader_id)
+}
+
+unsafe fn check_link_result(program_id: c_uint, file_path: &str) -> Result<(), ShaderError> {
+    let mut success = 0;
+    glGetProgramiv(program_id, GL_LINK_STATUS, &mut success);
+    if success == 0 {
+        let mut log_len = 0;
+        glGetProgramiv(program_id, GL_INFO_LOG_LENGTH, &mut log_len);
+        let mut log: Vec<u8> = vec![0; log_len.max(1) as usize];
+        glGetProgramInfoLog(program_id, log.len() as _, &mut log_len, log.as_mut_ptr().cast());
+        log.truncate(log_len.max(0) as usize);
+        return Err(ShaderError::Link {
+            file_path: file_path.to_string(),
+            log: String::from_utf8_lossy(&log).into_owned()
+        });
+    }
+    Ok(())
+}
+
+fn read_file(file_path: &str) -> io::Result<String> {
+    fs::read_to_string(file_path)
+}
+
+fn load_shader(file_path: &str) -> Result<String, ShaderError> {
+    fs::read_to_string(file_path).map_err(|error| ShaderError::Io {
+        file_path: file_path.to_string(),
+        error
+    })
+}
+
+fn to_owned_defines(defines: &[(&str, &str)]) -> Vec<(String, String)> {
+    defines.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+}
+
+// the shader file first, then every include once
+fn watch_files(sources: &[(ShaderStage, PreprocessedSource)]) -> Vec<(String, Option<SystemTime>)> {
+    let mut watched_files: Vec<(String, Option<SystemTime>)> = Vec::new();
+    for file_path in sources.iter().flat_map(|(_, source)| &source.files) {
+        if !watched_files.iter().any(|(path, _)| path == file_path) {
+            watched_files.push((file_path.clone(), file_modified(file_path)));
+        }
+    }
+    watched_files
+}
+
+fn file_modified(file_path: &str) -> Option<SystemTime> {
+    fs::metadata(file_path).and_then(|metadata| metadata.modified()).ok()
+}
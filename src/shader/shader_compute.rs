@@ -0,0 +1,47 @@
+use std::ffi::c_void;
+use std::sync::OnceLock;
+
+use gl33::*;
+
+use crate::shader::{gl_supports, load_proc};
+
+// OpenGL 4.3, not part of the gl33 bindings
+pub(crate) const GL_COMPUTE_SHADER: GLenum = GLenum(0x91B9);
+
+pub const GL_TEXTURE_FETCH_BARRIER_BIT: GLbitfield = GLbitfield(0x0000_0008);
+pub const GL_SHADER_IMAGE_ACCESS_BARRIER_BIT: GLbitfield = GLbitfield(0x0000_0020);
+pub const GL_BUFFER_UPDATE_BARRIER_BIT: GLbitfield = GLbitfield(0x0000_0200);
+pub const GL_SHADER_STORAGE_BARRIER_BIT: GLbitfield = GLbitfield(0x0000_2000);
+pub const GL_ALL_BARRIER_BITS: GLbitfield = GLbitfield(0xFFFF_FFFF);
+
+type DispatchComputeFn = unsafe extern "system" fn(u32, u32, u32);
+type MemoryBarrierFn = unsafe extern "system" fn(GLbitfield);
+
+static DISPATCH_COMPUTE: OnceLock<usize> = OnceLock::new();
+static MEMORY_BARRIER: OnceLock<usize> = OnceLock::new();
+
+/// True if the current context can run compute shaders, OpenGL 4.3 or `GL_ARB_compute_shader`.
+pub fn compute_supported() -> bool {
+    gl_supports((4, 3), "GL_ARB_compute_shader")
+}
+
+/// Waits for the writes of earlier dispatches, e.g. `GL_SHADER_STORAGE_BARRIER_BIT` before reading a buffer they wrote.
+pub fn memory_barrier(barriers: GLbitfield) {
+    let address = load_compute_proc(&MEMORY_BARRIER, b"glMemoryBarrier\0");
+    unsafe {
+        let memory_barrier = std::mem::transmute::<*const c_void, MemoryBarrierFn>(address);
+        memory_barrier(barriers);
+    }
+}
+
+pub(crate) fn dispatch_compute(groups: [u32; 3]) {
+    let address = load_compute_proc(&DISPATCH_COMPUTE, b"glDispatchCompute\0");
+    unsafe {
+        let dispatch_compute = std::mem::transmute::<*const c_void, DispatchComputeFn>(address);
+        dispatch_compute(groups[0], groups[1], groups[2]);
+    }
+}
+
+fn load_compute_proc(cache: &OnceLock<usize>, name: &'static [u8]) -> *const c_void {
+    load_proc(cache, name).expect("Error: compute shaders are not supported by this OpenGL context")
+}
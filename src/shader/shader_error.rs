@@ -0,0 +1,67 @@
+use std::fmt::{Display, Formatter};
+
+use crate::shader::ShaderStage;
+
+#[derive(Debug)]
+pub enum ShaderError {
+    // no shader of that name in the ShaderSet
+    Unknown {
+        name: String
+    },
+    // built from a source string, there is no file to reload it from
+    NotReloadable {
+        file_path: String
+    },
+    Io {
+        file_path: String,
+        error: std::io::Error
+    },
+    // line numbers start at 1
+    Parse {
+        file_path: String,
+        line: usize,
+        message: String
+    },
+    MissingStage {
+        file_path: String,
+        stage: ShaderStage
+    },
+    // e.g. compute shaders without OpenGL 4.3 or ARB_compute_shader
+    UnsupportedStage {
+        file_path: String,
+        stage: ShaderStage
+    },
+    Compile {
+        file_path: String,
+        stage: ShaderStage,
+        log: String
+    },
+    Link {
+        file_path: String,
+        log: String
+    }
+}
+
+impl Display for ShaderError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShaderError::Unknown { name } => write!(f, "{}: no shader registered with this name", name),
+            ShaderError::NotReloadable { file_path } => write!(f, "{}: not loaded from a file, can't be reloaded", file_path),
+            ShaderError::Io { file_path, error } => write!(f, "{}: couldn't read shader file: {}", file_path, error),
+            ShaderError::Parse { file_path, line, message } => write!(f, "{}:{}: {}", file_path, line, message),
+            ShaderError::MissingStage { file_path, stage } => write!(f, "{}: {} shader source not found", file_path, stage),
+            ShaderError::UnsupportedStage { file_path, stage } => write!(f, "{}: {} shaders are not supported by this OpenGL context", file_path, stage),
+            ShaderError::Compile { file_path, stage, log } => write!(f, "{}: {} shader compile error: {}", file_path, stage, log),
+            ShaderError::Link { file_path, log } => write!(f, "{}: program link error: {}", file_path, log)
+        }
+    }
+}
+
+impl std::error::Error for ShaderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ShaderError::Io { error, .. } => Some(error),
+            _ => None
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use std::ffi::{c_void, CStr};
+use std::sync::OnceLock;
+
+use gl33::*;
+use gl33::global_loader::*;
+
+/// True if the current context is at least OpenGL `version` or has `extension`, e.g. `(4, 3)` and `GL_ARB_compute_shader`.
+pub fn gl_supports(version: (i32, i32), extension: &str) -> bool {
+    unsafe {
+        let (mut major, mut minor) = (0, 0);
+        glGetIntegerv(GL_MAJOR_VERSION, &mut major);
+        glGetIntegerv(GL_MINOR_VERSION, &mut minor);
+        if (major, minor) >= version {
+            return true;
+        }
+        let mut extension_count = 0;
+        glGetIntegerv(GL_NUM_EXTENSIONS, &mut extension_count);
+        (0..extension_count.max(0) as u32).any(|index| {
+            let name = glGetStringi(GL_EXTENSIONS, index);
+            !name.is_null() && CStr::from_ptr(name.cast()).to_bytes() == extension.as_bytes()
+        })
+    }
+}
+
+// functions newer than OpenGL 3.3 aren't part of the gl33 bindings, they are loaded on first use from the
+// current context like the gl33 global loader does for everything else
+pub(crate) fn load_proc(cache: &OnceLock<usize>, name: &'static [u8]) -> Option<*const c_void> {
+    let address = match cache.get() {
+        Some(address) => *address,
+        None => {
+            let name = CStr::from_bytes_with_nul(name).unwrap();
+            let address = unsafe { glfw::ffi::glfwGetProcAddress(name.as_ptr()) as usize };
+            // a missing function may show up once a context is current
+            if address != 0 {
+                let _ = cache.set(address);
+            }
+            address
+        }
+    };
+    (address != 0).then_some(address as *const c_void)
+}
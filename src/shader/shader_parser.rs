@@ -0,0 +1,195 @@
+use std::fmt::{Display, Formatter};
+
+use gl33::*;
+
+use crate::shader::{ShaderError, GL_COMPUTE_SHADER};
+
+const TYPE_DIRECTIVE: &str = "#type";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShaderStage {
+    Vertex,
+    Geometry,
+    Fragment,
+    // only alone in its program
+    Compute
+}
+
+impl ShaderStage {
+    pub const ALL: [ShaderStage; 4] = [ShaderStage::Vertex, ShaderStage::Geometry, ShaderStage::Fragment, ShaderStage::Compute];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        ShaderStage::ALL.into_iter().find(|stage| stage.name() == name)
+    }
+
+    // as written after `#type`
+    pub fn name(&self) -> &'static str {
+        match self {
+            ShaderStage::Vertex => "vertex",
+            ShaderStage::Geometry => "geometry",
+            ShaderStage::Fragment => "fragment",
+            ShaderStage::Compute => "compute"
+        }
+    }
+
+    pub(crate) fn gl_type(&self) -> GLenum {
+        match self {
+            ShaderStage::Vertex => GL_VERTEX_SHADER,
+            ShaderStage::Geometry => GL_GEOMETRY_SHADER,
+            ShaderStage::Fragment => GL_FRAGMENT_SHADER,
+            ShaderStage::Compute => GL_COMPUTE_SHADER
+        }
+    }
+}
+
+impl Display for ShaderStage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShaderSection {
+    pub stage: ShaderStage,
+    pub source: String,
+    // line of the file the section source starts at, starting at 1
+    pub first_line: usize
+}
+
+/// Splits a shader file into its `#type <stage>` sections.
+///
+/// Sections can come in any order, a compute section can't be combined with other stages.
+/// Accepts LF and CRLF line endings, blank lines before and between sections are ignored.
+pub fn parse_shader(file_path: &str, source: &str) -> Result<Vec<ShaderSection>, ShaderError> {
+    let error = |line: usize, message: String| ShaderError::Parse {
+        file_path: file_path.to_string(),
+        line,
+        message
+    };
+
+    let mut sections: Vec<ShaderSection> = Vec::new();
+    // stage, line of the #type directive and the lines of the section so far
+    let mut current: Option<(ShaderStage, usize, Vec<&str>)> = None;
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let trimmed = line.trim();
+        let mut tokens = trimmed.split_whitespace();
+
+        if tokens.next() != Some(TYPE_DIRECTIVE) {
+            if trimmed.starts_with(TYPE_DIRECTIVE) {
+                return Err(error(line_number, format!("Expected a space after {}", TYPE_DIRECTIVE)));
+            }
+            match current.as_mut() {
+                Some((_, _, lines)) => lines.push(line),
+                None if trimmed.is_empty() => {}
+                None => return Err(error(line_number, format!("Expected {} before the shader source", TYPE_DIRECTIVE)))
+            }
+            continue;
+        }
+
+        let stage = match tokens.next() {
+            Some(name) => ShaderStage::from_name(name).ok_or_else(|| error(
+                line_number,
+                format!("Unknown shader type '{}', expected one of: {}", name, stage_names())
+            ))?,
+            None => return Err(error(line_number, format!("Missing shader type after {}", TYPE_DIRECTIVE)))
+        };
+        if let Some(token) = tokens.next() {
+            return Err(error(line_number, format!("Unexpected '{}' after shader type", token)));
+        }
+
+        if let Some(section) = current.take() {
+            sections.push(finish_section(section).map_err(|(line, message)| error(line, message))?);
+        }
+        let duplicate = sections.iter().find(|section| section.stage == stage);
+        if let Some(duplicate) = duplicate {
+            return Err(error(line_number, format!("Duplicate {} section, the first one starts at line {}", stage, duplicate.first_line)));
+        }
+        let mixes_compute = sections.iter().any(|section| (section.stage == ShaderStage::Compute) != (stage == ShaderStage::Compute));
+        if mixes_compute {
+            return Err(error(line_number, format!("A {} section can't be combined with other stages", ShaderStage::Compute)));
+        }
+        current = Some((stage, line_number, Vec::new()));
+    }
+
+    if let Some(section) = current.take() {
+        sections.push(finish_section(section).map_err(|(line, message)| error(line, message))?);
+    }
+    Ok(sections)
+}
+
+fn finish_section((stage, directive_line, lines): (ShaderStage, usize, Vec<&str>)) -> Result<ShaderSection, (usize, String)> {
+    let leading = lines.iter().take_while(|line| line.trim().is_empty()).count();
+    let trailing = lines.iter().rev().take_while(|line| line.trim().is_empty()).count();
+    if leading == lines.len() {
+        return Err((directive_line, format!("Empty {} section", stage)));
+    }
+    Ok(ShaderSection {
+        stage,
+        source: lines[leading..lines.len() - trailing].join("\n"),
+        first_line: directive_line + 1 + leading
+    })
+}
+
+fn stage_names() -> String {
+    ShaderStage::ALL.map(|stage| stage.name()).join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::shader::ShaderError;
+
+    use super::{parse_shader, ShaderStage};
+
+    const SOURCE: &str = "#type vertex\n#version 330 core\nvoid main() {}\n\n#type fragment\n\n#version 330 core\nvoid main() {}\n";
+
+    fn parse_error(source: &str) -> (usize, String) {
+        match parse_shader("test.glsl", source) {
+            Err(ShaderError::Parse { line, message, .. }) => (line, message),
+            other => panic!("Expected a parse error, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn parses_lf_and_crlf_the_same() {
+        let lf = parse_shader("test.glsl", SOURCE).unwrap();
+        let crlf = parse_shader("test.glsl", &SOURCE.replace('\n', "\r\n")).unwrap();
+        assert_eq!(lf, crlf);
+
+        assert_eq!(lf.len(), 2);
+        assert_eq!(lf[0].stage, ShaderStage::Vertex);
+        assert_eq!(lf[0].source, "#version 330 core\nvoid main() {}");
+        assert_eq!(lf[0].first_line, 2);
+        assert_eq!(lf[1].stage, ShaderStage::Fragment);
+        assert_eq!(lf[1].source, "#version 330 core\nvoid main() {}");
+        assert_eq!(lf[1].first_line, 7);
+    }
+
+    #[test]
+    fn reports_errors_with_line_numbers() {
+        assert_eq!(parse_error("\n#type tessellation\nvoid main() {}").0, 2);
+        assert_eq!(parse_error("void main() {}\n#type vertex\n").0, 1);
+        assert_eq!(parse_error("#type vertex\nvoid main() {}\n#type\n").0, 3);
+        assert_eq!(parse_error("#type vertex\nvoid main() {}\n#typefragment\n").0, 3);
+        assert_eq!(parse_error("#type vertex fragment\n").0, 1);
+        assert_eq!(parse_error("#type vertex\n\n#type fragment\nvoid main() {}").0, 1);
+
+        let (line, message) = parse_error("#type vertex\na\n#type fragment\nb\n#type vertex\nc");
+        assert_eq!(line, 5);
+        assert!(message.contains("line 2"), "{}", message);
+
+        assert_eq!(parse_error("#type compute\na\n#type vertex\nb").0, 3);
+        assert_eq!(parse_error("#type fragment\na\n#type compute\nb").0, 3);
+    }
+
+    #[test]
+    fn parses_sections_in_any_order() {
+        let sections = parse_shader("test.glsl", "#type fragment\na\n#type geometry\nb\n#type vertex\nc").unwrap();
+        let stages: Vec<ShaderStage> = sections.iter().map(|section| section.stage).collect();
+        assert_eq!(stages, [ShaderStage::Fragment, ShaderStage::Geometry, ShaderStage::Vertex]);
+
+        let sections = parse_shader("test.glsl", "#type compute\nvoid main() {}").unwrap();
+        assert_eq!(sections[0].stage, ShaderStage::Compute);
+    }
+}
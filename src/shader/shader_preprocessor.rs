@@ -0,0 +1,264 @@
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use crate::shader::{ShaderError, ShaderSection};
+
+const INCLUDE_DIRECTIVE: &str = "#include";
+const VERSION_DIRECTIVE: &str = "#version";
+
+/// Source of one shader stage with its includes and defines resolved.
+///
+/// Every file gets its own source string number in the `#line` directives, so the driver reports
+/// errors with the line of the original file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreprocessedSource {
+    pub source: String,
+    // file of each source string number, the shader file itself is 0
+    pub files: Vec<String>
+}
+
+impl PreprocessedSource {
+    /// Replaces the source string numbers in a compile log with the files they stand for.
+    pub fn map_log(&self, log: &str) -> String {
+        log.lines()
+            .map(|line| self.map_log_line(line).unwrap_or_else(|| line.to_string()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// File and line that line `line` of `source` came from, counting from 1. None for lines the preprocessor added.
+    pub fn source_line(&self, line: usize) -> Option<(&str, usize)> {
+        // (file index, line) of the next line
+        let mut next: Option<(usize, usize)> = None;
+        for (index, text) in self.source.lines().enumerate() {
+            let directive = parse_line_directive(text);
+            if index + 1 == line {
+                let (file, line) = next.filter(|_| directive.is_none())?;
+                return Some((self.files.get(file)?.as_str(), line));
+            }
+            next = directive.or(next.map(|(file, line)| (file, line + 1)));
+        }
+        None
+    }
+
+    // "0:12(5): error" (Mesa), "0(12) : error" (NVIDIA), "ERROR: 0:12: error" (AMD, Intel, Apple)
+    fn map_log_line(&self, line: &str) -> Option<String> {
+        let (prefix, rest) = line.split_at(line.find(|c: char| c.is_ascii_digit())?);
+        if !prefix.is_empty() && !prefix.ends_with(": ") {
+            return None;
+        }
+        let (number, rest) = rest.split_at(rest.find(|c: char| !c.is_ascii_digit())?);
+        let mut chars = rest.chars();
+        let separator = chars.next().filter(|c| *c == ':' || *c == '(')?;
+        if !chars.next()?.is_ascii_digit() {
+            return None;
+        }
+        let file = self.files.get(number.parse::<usize>().ok()?)?;
+        Some(format!("{}{}{}{}", prefix, file, separator, &rest[1..]))
+    }
+}
+
+/// Resolves the `#include "file"` directives of a shader section and adds `defines` after its `#version`.
+///
+/// Includes are relative to the including file and read with `read_file`, they may include other files but not
+/// themselves. Relies on `#line` meaning "the next line" as in GLSL 3.30 and newer.
+pub fn preprocess(
+    file_path: &str,
+    section: &ShaderSection,
+    defines: &[(String, String)],
+    read_file: &dyn Fn(&str) -> io::Result<String>
+) -> Result<PreprocessedSource, ShaderError> {
+    let mut preprocessor = Preprocessor {
+        read_file,
+        files: vec![file_path.to_string()],
+        include_stack: vec![normalize_path(Path::new(file_path))],
+        lines: Vec::new()
+    };
+
+    let lines: Vec<&str> = section.source.lines().collect();
+    // #version has to stay the first directive
+    let body_start = lines
+        .iter()
+        .position(|line| line.trim_start().starts_with(VERSION_DIRECTIVE))
+        .map_or(0, |index| index + 1);
+    preprocessor.lines.extend(lines[..body_start].iter().map(|line| line.to_string()));
+    for (name, value) in defines {
+        preprocessor.lines.push(format!("#define {} {}", name, value).trim_end().to_string());
+    }
+    preprocessor.push_file(file_path, 0, &lines[body_start..], section.first_line + body_start)?;
+
+    Ok(PreprocessedSource {
+        source: preprocessor.lines.join("\n"),
+        files: preprocessor.files
+    })
+}
+
+struct Preprocessor<'a> {
+    read_file: &'a dyn Fn(&str) -> io::Result<String>,
+    files: Vec<String>,
+    // files currently being included, to detect cycles
+    include_stack: Vec<String>,
+    lines: Vec<String>
+}
+
+impl Preprocessor<'_> {
+    fn push_file(&mut self, file_path: &str, file_index: usize, lines: &[&str], first_line: usize) -> Result<(), ShaderError> {
+        let error = |line: usize, message: String| ShaderError::Parse {
+            file_path: file_path.to_string(),
+            line,
+            message
+        };
+
+        self.lines.push(format!("#line {} {}", first_line, file_index));
+        for (offset, line) in lines.iter().enumerate() {
+            let line_number = first_line + offset;
+            let trimmed = line.trim();
+            if !trimmed.starts_with(INCLUDE_DIRECTIVE) {
+                self.lines.push(line.to_string());
+                continue;
+            }
+
+            let include = parse_include(trimmed)
+                .ok_or_else(|| error(line_number, format!("Expected {} \"file\"", INCLUDE_DIRECTIVE)))?;
+            let include_path = resolve_include(file_path, include);
+            if self.include_stack.contains(&include_path) {
+                return Err(error(line_number, format!("Include cycle: {} -> {}", self.include_stack.join(" -> "), include_path)));
+            }
+            let source = (self.read_file)(&include_path)
+                .map_err(|err| error(line_number, format!("Couldn't read '{}': {}", include_path, err)))?;
+
+            let include_index = self.files.len();
+            self.files.push(include_path.clone());
+            self.include_stack.push(include_path.clone());
+            let include_lines: Vec<&str> = source.lines().collect();
+            self.push_file(&include_path, include_index, &include_lines, 1)?;
+            self.include_stack.pop();
+
+            // continue after the #include line
+            self.lines.push(format!("#line {} {}", line_number + 1, file_index));
+        }
+        Ok(())
+    }
+}
+
+// "#line 12 1" as written by `push_file`
+fn parse_line_directive(line: &str) -> Option<(usize, usize)> {
+    let mut parts = line.strip_prefix("#line ")?.split(' ');
+    let line = parts.next()?.parse().ok()?;
+    let file = parts.next()?.parse().ok()?;
+    Some((file, line))
+}
+
+fn parse_include(line: &str) -> Option<&str> {
+    let rest = line.strip_prefix(INCLUDE_DIRECTIVE)?;
+    if !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    rest.trim()
+        .strip_prefix('"')?
+        .strip_suffix('"')
+        .filter(|include| !include.is_empty())
+}
+
+fn resolve_include(including_file: &str, include: &str) -> String {
+    let directory = Path::new(including_file).parent().unwrap_or(Path::new(""));
+    normalize_path(&directory.join(include))
+}
+
+// lexically removes `.` and `..`, the same file is always spelled the same way
+fn normalize_path(path: &Path) -> String {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if matches!(normalized.components().next_back(), Some(Component::Normal(_))) => {
+                normalized.pop();
+            }
+            component => normalized.push(component)
+        }
+    }
+    normalized.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::io;
+
+    use crate::shader::{parse_shader, ShaderError, ShaderStage};
+
+    use super::{preprocess, PreprocessedSource};
+
+    fn preprocess_vertex(files: &[(&str, &str)], defines: &[(&str, &str)]) -> Result<PreprocessedSource, ShaderError> {
+        let files: HashMap<&str, &str> = files.iter().copied().collect();
+        let read_file = |path: &str| files
+            .get(path)
+            .map(|source| source.to_string())
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound));
+        let sections = parse_shader("shaders/main.glsl", &read_file("shaders/main.glsl").unwrap()).unwrap();
+        let section = sections.iter().find(|section| section.stage == ShaderStage::Vertex).unwrap();
+        let defines: Vec<(String, String)> = defines.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect();
+        preprocess("shaders/main.glsl", section, &defines, &read_file)
+    }
+
+    #[test]
+    fn resolves_includes_and_defines() {
+        let result = preprocess_vertex(&[
+            ("shaders/main.glsl", "#type vertex\n#version 330 core\n#include \"lib/color.glsl\"\nvoid main() {}\n"),
+            ("shaders/lib/color.glsl", "#include \"../common.glsl\"\nvec3 color() { return vec3(1.0); }"),
+            ("shaders/common.glsl", "#define PI 3.14159")
+        ], &[("MAX_LIGHTS", "4"), ("SHADOWS", "")]).unwrap();
+
+        assert_eq!(result.files, ["shaders/main.glsl", "shaders/lib/color.glsl", "shaders/common.glsl"]);
+        assert_eq!(result.source, [
+            "#version 330 core",
+            "#define MAX_LIGHTS 4",
+            "#define SHADOWS",
+            "#line 3 0",
+            "#line 1 1",
+            "#line 1 2",
+            "#define PI 3.14159",
+            "#line 2 1",
+            "vec3 color() { return vec3(1.0); }",
+            "#line 4 0",
+            "void main() {}"
+        ].join("\n"));
+
+        let log = "0:4(9): error: `x' undeclared\n1(2) : error C0000: syntax error\nERROR: 2:1: ''\nlinker stuff 5:3";
+        assert_eq!(result.map_log(log), [
+            "shaders/main.glsl:4(9): error: `x' undeclared",
+            "shaders/lib/color.glsl(2) : error C0000: syntax error",
+            "ERROR: shaders/common.glsl:1: ''",
+            "linker stuff 5:3"
+        ].join("\n"));
+
+        assert_eq!(result.source_line(2), None);
+        assert_eq!(result.source_line(6), None);
+        assert_eq!(result.source_line(7), Some(("shaders/common.glsl", 1)));
+        assert_eq!(result.source_line(9), Some(("shaders/lib/color.glsl", 2)));
+        assert_eq!(result.source_line(11), Some(("shaders/main.glsl", 4)));
+        assert_eq!(result.source_line(12), None);
+    }
+
+    #[test]
+    fn reports_include_errors() {
+        let cycle = preprocess_vertex(&[
+            ("shaders/main.glsl", "#type vertex\n#include \"a.glsl\"\n"),
+            ("shaders/a.glsl", "#include \"./b.glsl\""),
+            ("shaders/b.glsl", "\n#include \"../shaders/a.glsl\"")
+        ], &[]);
+        match cycle {
+            Err(ShaderError::Parse { file_path, line, message }) => {
+                assert_eq!((file_path.as_str(), line), ("shaders/b.glsl", 2));
+                assert!(message.contains("shaders/main.glsl -> shaders/a.glsl -> shaders/b.glsl -> shaders/a.glsl"), "{}", message);
+            }
+            other => panic!("Expected a parse error, got {:?}", other)
+        }
+
+        let missing = preprocess_vertex(&[("shaders/main.glsl", "#type vertex\n\n#include \"missing.glsl\"\n")], &[]);
+        assert!(matches!(missing, Err(ShaderError::Parse { line: 3, .. })), "{:?}", missing);
+
+        let malformed = preprocess_vertex(&[("shaders/main.glsl", "#type vertex\n#include <a.glsl>\n")], &[]);
+        assert!(matches!(malformed, Err(ShaderError::Parse { line: 2, .. })), "{:?}", malformed);
+    }
+}
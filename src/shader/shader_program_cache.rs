@@ -0,0 +1,203 @@
+use std::cell::{OnceCell, RefCell};
+use std::ffi::{c_void, CStr};
+use std::fs;
+use std::os::raw::c_uint;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use gl33::*;
+use gl33::global_loader::*;
+
+use crate::shader::{gl_supports, load_proc, PreprocessedSource, ShaderStage};
+
+// OpenGL 4.1, not part of the gl33 bindings
+const GL_PROGRAM_BINARY_RETRIEVABLE_HINT: GLenum = GLenum(0x8257);
+const GL_PROGRAM_BINARY_LENGTH: GLenum = GLenum(0x8741);
+const GL_NUM_PROGRAM_BINARY_FORMATS: GLenum = GLenum(0x87FE);
+const GL_PROGRAM_BINARY_FORMATS: GLenum = GLenum(0x87FF);
+
+type GetProgramBinaryFn = unsafe extern "system" fn(u32, i32, *mut i32, *mut GLenum, *mut c_void);
+type ProgramBinaryFn = unsafe extern "system" fn(u32, GLenum, *const c_void, i32);
+type ProgramParameteriFn = unsafe extern "system" fn(u32, GLenum, i32);
+
+static GET_PROGRAM_BINARY: OnceLock<usize> = OnceLock::new();
+static PROGRAM_BINARY: OnceLock<usize> = OnceLock::new();
+static PROGRAM_PARAMETERI: OnceLock<usize> = OnceLock::new();
+
+thread_local! {
+    // like the GL context, the cache belongs to the thread that builds the shaders
+    static PROGRAM_CACHE: RefCell<Option<ProgramCache>> = const { RefCell::new(None) };
+}
+
+struct ProgramCache {
+    directory: PathBuf,
+    // vendor, renderer and version of the driver, None if it can't hand out program binaries
+    driver: OnceCell<Option<String>>
+}
+
+/// Caches linked programs in `directory` and loads them from there on the next start instead of compiling.
+///
+/// Needs OpenGL 4.1 or `GL_ARB_get_program_binary`, without them shaders are compiled as before. A binary the
+/// driver rejects, e.g. after a driver update, is compiled again and replaced.
+pub fn enable_program_cache(directory: impl Into<PathBuf>) {
+    let cache = ProgramCache { directory: directory.into(), driver: OnceCell::new() };
+    PROGRAM_CACHE.with(|program_cache| *program_cache.borrow_mut() = Some(cache));
+}
+
+pub fn disable_program_cache() {
+    PROGRAM_CACHE.with(|program_cache| *program_cache.borrow_mut() = None);
+}
+
+/// The cache file of one program.
+pub(crate) struct CachedProgram {
+    path: PathBuf
+}
+
+// None if the cache is disabled or the driver doesn't support program binaries
+pub(crate) fn cached_program(sources: &[(ShaderStage, PreprocessedSource)]) -> Option<CachedProgram> {
+    PROGRAM_CACHE.with(|program_cache| {
+        let program_cache = program_cache.borrow();
+        let program_cache = program_cache.as_ref()?;
+        let driver = program_cache.driver.get_or_init(driver_name).as_deref()?;
+        let path = program_cache.directory.join(format!("{:016x}.bin", cache_key(driver, sources)));
+        Some(CachedProgram { path })
+    })
+}
+
+impl CachedProgram {
+    /// The cached program, None if there is none or the driver rejected it.
+    pub(crate) fn load(&self) -> Option<c_uint> {
+        let bytes = fs::read(&self.path).ok()?;
+        if bytes.len() <= 4 {
+            return None;
+        }
+        let format = GLenum(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+        let binary = &bytes[4..];
+        // glProgramBinary raises GL_INVALID_ENUM for a format the driver doesn't list, so it never sees one
+        if !binary_formats().contains(&format) {
+            let _ = fs::remove_file(&self.path);
+            return None;
+        }
+        let address = load_proc(&PROGRAM_BINARY, b"glProgramBinary\0")?;
+        unsafe {
+            let program_binary = std::mem::transmute::<*const c_void, ProgramBinaryFn>(address);
+            let program_id = glCreateProgram();
+            assert_ne!(program_id, 0);
+            program_binary(program_id, format, binary.as_ptr().cast(), binary.len() as _);
+            let mut success = 0;
+            glGetProgramiv(program_id, GL_LINK_STATUS, &mut success);
+            if success != 0 {
+                return Some(program_id);
+            }
+            glDeleteProgram(program_id);
+        }
+        let _ = fs::remove_file(&self.path);
+        None
+    }
+
+    /// Asks the driver to keep the binary, call before linking.
+    pub(crate) fn prepare(&self, program_id: c_uint) {
+        if let Some(address) = load_proc(&PROGRAM_PARAMETERI, b"glProgramParameteri\0") {
+            unsafe {
+                let program_parameteri = std::mem::transmute::<*const c_void, ProgramParameteriFn>(address);
+                program_parameteri(program_id, GL_PROGRAM_BINARY_RETRIEVABLE_HINT, GL_TRUE.0 as _);
+            }
+        }
+    }
+
+    /// Writes the binary of a linked program, failing to do so only costs a compile on the next start.
+    pub(crate) fn store(&self, program_id: c_uint) {
+        let Some(address) = load_proc(&GET_PROGRAM_BINARY, b"glGetProgramBinary\0") else { return };
+        let mut bytes;
+        unsafe {
+            let get_program_binary = std::mem::transmute::<*const c_void, GetProgramBinaryFn>(address);
+            let mut length = 0;
+            glGetProgramiv(program_id, GL_PROGRAM_BINARY_LENGTH, &mut length);
+            if length <= 0 {
+                return;
+            }
+            bytes = vec![0; 4 + length as usize];
+            let (mut written, mut format) = (0, GLenum(0));
+            get_program_binary(program_id, length, &mut written, &mut format, bytes[4..].as_mut_ptr().cast());
+            bytes.truncate(4 + written.max(0) as usize);
+            bytes[..4].copy_from_slice(&format.0.to_le_bytes());
+        }
+
+        // written next to the final file and renamed, so a crash never leaves half a binary behind
+        let temp_path = self.path.with_extension("tmp");
+        let result = fs::create_dir_all(self.path.parent().unwrap())
+            .and_then(|_| fs::write(&temp_path, &bytes))
+            .and_then(|_| fs::rename(&temp_path, &self.path));
+        if let Err(err) = result {
+            eprintln!("Warning: couldn't write the program cache {}: {}", self.path.display(), err);
+        }
+    }
+}
+
+fn driver_name() -> Option<String> {
+    if !gl_supports((4, 1), "GL_ARB_get_program_binary") {
+        return None;
+    }
+    if binary_formats().is_empty() {
+        return None;
+    }
+    unsafe {
+        let names: Vec<String> = [GL_VENDOR, GL_RENDERER, GL_VERSION]
+            .iter()
+            .map(|name| {
+                let value = glGetString(*name);
+                if value.is_null() { String::new() } else { CStr::from_ptr(value.cast()).to_string_lossy().into_owned() }
+            })
+            .collect();
+        Some(names.join("\n"))
+    }
+}
+
+fn binary_formats() -> Vec<GLenum> {
+    unsafe {
+        let mut format_count = 0;
+        glGetIntegerv(GL_NUM_PROGRAM_BINARY_FORMATS, &mut format_count);
+        let mut formats = vec![0; format_count.max(0) as usize];
+        if !formats.is_empty() {
+            glGetIntegerv(GL_PROGRAM_BINARY_FORMATS, formats.as_mut_ptr());
+        }
+        formats.into_iter().map(|format| GLenum(format as u32)).collect()
+    }
+}
+
+// FNV-1a, unlike DefaultHasher it stays the same across Rust versions. The sources already contain the defines.
+fn cache_key(driver: &str, sources: &[(ShaderStage, PreprocessedSource)]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut write = |bytes: &[u8]| {
+        for byte in bytes.iter().chain(&[0]) {
+            hash = (hash ^ *byte as u64).wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    };
+    write(driver.as_bytes());
+    for (stage, source) in sources {
+        write(stage.name().as_bytes());
+        write(source.source.as_bytes());
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::shader::{PreprocessedSource, ShaderStage};
+
+    use super::cache_key;
+
+    #[test]
+    fn keys_depend_on_driver_stages_and_sources() {
+        let stage = |stage, source: &str| (stage, PreprocessedSource { source: source.to_string(), files: Vec::new() });
+        let sources = [stage(ShaderStage::Vertex, "#version 330 core\nvoid main() {}"), stage(ShaderStage::Fragment, "void main() {}")];
+        let key = cache_key("Mesa\nllvmpipe\n4.5", &sources);
+
+        assert_eq!(key, cache_key("Mesa\nllvmpipe\n4.5", &sources));
+        assert_ne!(key, cache_key("Mesa\nllvmpipe\n4.6", &sources));
+        let defined = [stage(ShaderStage::Vertex, "#version 330 core\n#define SHADOWS\nvoid main() {}"), sources[1].clone()];
+        assert_ne!(key, cache_key("Mesa\nllvmpipe\n4.5", &defined));
+        let swapped = [stage(ShaderStage::Fragment, "#version 330 core\nvoid main() {}"), stage(ShaderStage::Vertex, "void main() {}")];
+        assert_ne!(key, cache_key("Mesa\nllvmpipe\n4.5", &swapped));
+    }
+}
@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::os::raw::{c_int, c_uint};
+
+use gl33::*;
+use gl33::global_loader::*;
+
+use crate::shader::uniform_block_binding;
+
+/// An active uniform or vertex attribute of a linked program.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShaderVariable {
+    // arrays are named without the trailing [0]
+    pub name: String,
+    pub location: c_int,
+    pub gl_type: GLenum,
+    // number of array elements, 1 for everything else
+    pub size: usize,
+    // location of every array element, GL doesn't promise they are consecutive
+    pub element_locations: Vec<c_int>
+}
+
+impl ShaderVariable {
+    pub fn type_name(&self) -> &'static str {
+        gl_type_name(self.gl_type)
+    }
+}
+
+/// An active uniform block of a linked program.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShaderUniformBlock {
+    pub name: String,
+    pub index: c_uint,
+    // minimum buffer size in bytes
+    pub size: usize,
+    pub binding: u32
+}
+
+/// The active uniforms, uniform blocks and attributes of a program, queried once after linking.
+///
+/// Uniforms inside uniform blocks have no location and are only listed as part of their block.
+#[derive(Debug, Clone, Default)]
+pub struct ShaderReflection {
+    uniforms: HashMap<String, ShaderVariable>,
+    uniform_blocks: HashMap<String, ShaderUniformBlock>,
+    attributes: HashMap<String, ShaderVariable>
+}
+
+impl ShaderReflection {
+    pub(crate) fn query(program_id: c_uint) -> Self {
+        unsafe {
+            let mut uniforms = HashMap::new();
+            for (name, size, gl_type) in active_variables(program_id, GL_ACTIVE_UNIFORMS, GL_ACTIVE_UNIFORM_MAX_LENGTH, glGetActiveUniform) {
+                let location = glGetUniformLocation(program_id, CString::new(name.as_str()).unwrap().as_ptr().cast());
+                if location == -1 {
+                    continue;
+                }
+                let name = name.strip_suffix("[0]").unwrap_or(&name).to_string();
+                let element_locations = if size > 1 {
+                    (0..size).map(|index| {
+                        let element_name = CString::new(format!("{}[{}]", name, index)).unwrap();
+                        glGetUniformLocation(program_id, element_name.as_ptr().cast())
+                    }).collect()
+                } else {
+                    vec![location]
+                };
+                uniforms.insert(name.clone(), ShaderVariable { name, location, gl_type, size, element_locations });
+            }
+
+            let mut attributes = HashMap::new();
+            for (name, size, gl_type) in active_variables(program_id, GL_ACTIVE_ATTRIBUTES, GL_ACTIVE_ATTRIBUTE_MAX_LENGTH, glGetActiveAttrib) {
+                // built-ins like gl_VertexID
+                let location = glGetAttribLocation(program_id, CString::new(name.as_str()).unwrap().as_ptr().cast());
+                if location == -1 {
+                    continue;
+                }
+                let name = name.strip_suffix("[0]").unwrap_or(&name).to_string();
+                let element_locations = (0..size as c_int).map(|index| location + index).collect();
+                attributes.insert(name.clone(), ShaderVariable { name, location, gl_type, size, element_locations });
+            }
+
+            let mut uniform_blocks = HashMap::new();
+            let mut block_count = 0;
+            glGetProgramiv(program_id, GL_ACTIVE_UNIFORM_BLOCKS, &mut block_count);
+            for index in 0..block_count.max(0) as c_uint {
+                let (mut name_length, mut size) = (0, 0);
+                glGetActiveUniformBlockiv(program_id, index, GL_UNIFORM_BLOCK_NAME_LENGTH, &mut name_length);
+                glGetActiveUniformBlockiv(program_id, index, GL_UNIFORM_BLOCK_DATA_SIZE, &mut size);
+                let mut name: Vec<u8> = vec![0; name_length.max(1) as usize];
+                let mut length = 0;
+                glGetActiveUniformBlockName(program_id, index, name.len() as _, &mut length, name.as_mut_ptr().cast());
+                let name = String::from_utf8_lossy(&name[..length.max(0) as usize]).into_owned();
+                let binding = uniform_block_binding(&name);
+                uniform_blocks.insert(name.clone(), ShaderUniformBlock { name, index, size: size.max(0) as usize, binding });
+            }
+
+            ShaderReflection { uniforms, uniform_blocks, attributes }
+        }
+    }
+
+    pub fn uniforms(&self) -> impl Iterator<Item = &ShaderVariable> {
+        self.uniforms.values()
+    }
+
+    pub fn uniform_blocks(&self) -> impl Iterator<Item = &ShaderUniformBlock> {
+        self.uniform_blocks.values()
+    }
+
+    pub fn uniform_block(&self, name: &str) -> Option<&ShaderUniformBlock> {
+        self.uniform_blocks.get(name)
+    }
+
+    pub fn attributes(&self) -> impl Iterator<Item = &ShaderVariable> {
+        self.attributes.values()
+    }
+
+    pub fn uniform(&self, name: &str) -> Option<&ShaderVariable> {
+        self.uniforms.get(name)
+    }
+
+    /// Like `uniform`, but `name` may also be an array element like `lights[2]`. Returns the uniform and the index of the element.
+    pub fn uniform_element(&self, name: &str) -> Option<(&ShaderVariable, usize)> {
+        if let Some(uniform) = self.uniforms.get(name) {
+            return Some((uniform, 0));
+        }
+        let (array_name, index) = split_array_index(name)?;
+        self.uniforms.get(array_name).filter(|array| index < array.size).map(|array| (array, index))
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&ShaderVariable> {
+        self.attributes.get(name)
+    }
+}
+
+type GetActiveFn = unsafe fn(u32, u32, i32, *mut i32, *mut i32, *mut GLenum, *mut u8);
+
+// (name, array size, type) of every active uniform or attribute
+unsafe fn active_variables(program_id: c_uint, count_name: GLenum, max_length_name: GLenum, get_active: GetActiveFn) -> Vec<(String, usize, GLenum)> {
+    let mut count = 0;
+    glGetProgramiv(program_id, count_name, &mut count);
+    let mut max_length = 0;
+    glGetProgramiv(program_id, max_length_name, &mut max_length);
+
+    let mut variables = Vec::new();
+    let mut name: Vec<u8> = vec![0; max_length.max(1) as usize];
+    for index in 0..count.max(0) as u32 {
+        let (mut length, mut size, mut gl_type) = (0, 0, GLenum(0));
+        get_active(program_id, index, name.len() as _, &mut length, &mut size, &mut gl_type, name.as_mut_ptr().cast());
+        let variable_name = String::from_utf8_lossy(&name[..length.max(0) as usize]).into_owned();
+        variables.push((variable_name, size.max(1) as usize, gl_type));
+    }
+    variables
+}
+
+// "lights[2]" -> ("lights", 2)
+fn split_array_index(name: &str) -> Option<(&str, usize)> {
+    let (array_name, index) = name.strip_suffix(']')?.rsplit_once('[')?;
+    Some((array_name, index.parse().ok()?))
+}
+
+/// GLSL name of a uniform or attribute type, for messages.
+#[allow(non_upper_case_globals)]
+pub fn gl_type_name(gl_type: GLenum) -> &'static str {
+    match gl_type {
+        GL_FLOAT => "float",
+        GL_FLOAT_VEC2 => "vec2",
+        GL_FLOAT_VEC3 => "vec3",
+        GL_FLOAT_VEC4 => "vec4",
+        GL_INT => "int",
+        GL_INT_VEC2 => "ivec2",
+        GL_INT_VEC3 => "ivec3",
+        GL_INT_VEC4 => "ivec4",
+        GL_UNSIGNED_INT => "uint",
+        GL_UNSIGNED_INT_VEC2 => "uvec2",
+        GL_UNSIGNED_INT_VEC3 => "uvec3",
+        GL_UNSIGNED_INT_VEC4 => "uvec4",
+        GL_BOOL => "bool",
+        GL_BOOL_VEC2 => "bvec2",
+        GL_BOOL_VEC3 => "bvec3",
+        GL_BOOL_VEC4 => "bvec4",
+        GL_FLOAT_MAT2 => "mat2",
+        GL_FLOAT_MAT3 => "mat3",
+        GL_FLOAT_MAT4 => "mat4",
+        GL_FLOAT_MAT2x3 => "mat2x3",
+        GL_FLOAT_MAT2x4 => "mat2x4",
+        GL_FLOAT_MAT3x2 => "mat3x2",
+        GL_FLOAT_MAT3x4 => "mat3x4",
+        GL_FLOAT_MAT4x2 => "mat4x2",
+        GL_FLOAT_MAT4x3 => "mat4x3",
+        GL_SAMPLER_1D => "sampler1D",
+        GL_SAMPLER_2D => "sampler2D",
+        GL_SAMPLER_3D => "sampler3D",
+        GL_SAMPLER_CUBE => "samplerCube",
+        GL_SAMPLER_2D_SHADOW => "sampler2DShadow",
+        GL_SAMPLER_2D_ARRAY => "sampler2DArray",
+        GL_SAMPLER_2D_MULTISAMPLE => "sampler2DMS",
+        GL_SAMPLER_2D_RECT => "sampler2DRect",
+        GL_SAMPLER_BUFFER => "samplerBuffer",
+        GL_INT_SAMPLER_2D => "isampler2D",
+        GL_UNSIGNED_INT_SAMPLER_2D => "usampler2D",
+        _ => "unknown type"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::split_array_index;
+
+    #[test]
+    fn splits_array_element_names() {
+        assert_eq!(split_array_index("lights[2]"), Some(("lights", 2)));
+        assert_eq!(split_array_index("scene.lights[10]"), Some(("scene.lights", 10)));
+        assert_eq!(split_array_index("lights[2].color"), None);
+        assert_eq!(split_array_index("lights[]"), None);
+        assert_eq!(split_array_index("lights"), None);
+    }
+}
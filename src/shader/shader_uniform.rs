@@ -0,0 +1,193 @@
+use std::os::raw::c_int;
+
+use cgmath::{Matrix2, Matrix3, Matrix4, Vector2, Vector3, Vector4};
+use gl33::*;
+use gl33::global_loader::*;
+
+pub const SAMPLER_TYPES: &[GLenum] = &[
+    GL_SAMPLER_1D, GL_SAMPLER_2D, GL_SAMPLER_3D, GL_SAMPLER_CUBE,
+    GL_SAMPLER_1D_SHADOW, GL_SAMPLER_2D_SHADOW, GL_SAMPLER_CUBE_SHADOW,
+    GL_SAMPLER_1D_ARRAY, GL_SAMPLER_2D_ARRAY, GL_SAMPLER_1D_ARRAY_SHADOW, GL_SAMPLER_2D_ARRAY_SHADOW,
+    GL_SAMPLER_2D_MULTISAMPLE, GL_SAMPLER_2D_MULTISAMPLE_ARRAY, GL_SAMPLER_BUFFER, GL_SAMPLER_2D_RECT, GL_SAMPLER_2D_RECT_SHADOW,
+    GL_INT_SAMPLER_1D, GL_INT_SAMPLER_2D, GL_INT_SAMPLER_3D, GL_INT_SAMPLER_CUBE,
+    GL_INT_SAMPLER_1D_ARRAY, GL_INT_SAMPLER_2D_ARRAY, GL_INT_SAMPLER_2D_MULTISAMPLE, GL_INT_SAMPLER_2D_MULTISAMPLE_ARRAY,
+    GL_INT_SAMPLER_BUFFER, GL_INT_SAMPLER_2D_RECT,
+    GL_UNSIGNED_INT_SAMPLER_1D, GL_UNSIGNED_INT_SAMPLER_2D, GL_UNSIGNED_INT_SAMPLER_3D, GL_UNSIGNED_INT_SAMPLER_CUBE,
+    GL_UNSIGNED_INT_SAMPLER_1D_ARRAY, GL_UNSIGNED_INT_SAMPLER_2D_ARRAY, GL_UNSIGNED_INT_SAMPLER_2D_MULTISAMPLE,
+    GL_UNSIGNED_INT_SAMPLER_2D_MULTISAMPLE_ARRAY, GL_UNSIGNED_INT_SAMPLER_BUFFER, GL_UNSIGNED_INT_SAMPLER_2D_RECT
+];
+
+/// Texture unit a sampler uniform reads from, e.g. `shader.set("uSampler", TextureUnit(0))`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextureUnit(pub u32);
+
+/// A single GLSL value, also usable as an element of a uniform array.
+pub trait UniformValue: Sized {
+    // true for the GLSL types the value can be uploaded to
+    fn accepts(gl_type: GLenum) -> bool;
+
+    /// Uploads `values` to consecutive array elements starting at `location`.
+    ///
+    /// # Safety
+    /// `location` has to belong to the current program and have room for `values.len()` elements.
+    unsafe fn upload_slice(location: c_int, values: &[Self]);
+}
+
+/// Anything `Shader::set` accepts: a `UniformValue`, or a slice, array or `Vec` of them for uniform arrays.
+pub trait Uniform {
+    fn accepts(&self, gl_type: GLenum) -> bool;
+
+    fn element_count(&self) -> usize;
+
+    /// Uploads the first `count` elements.
+    ///
+    /// # Safety
+    /// `location` has to belong to the current program and have room for `count` elements.
+    unsafe fn upload(&self, location: c_int, count: usize);
+}
+
+impl<T: UniformValue> Uniform for T {
+    fn accepts(&self, gl_type: GLenum) -> bool {
+        T::accepts(gl_type)
+    }
+    fn element_count(&self) -> usize {
+        1
+    }
+    unsafe fn upload(&self, location: c_int, count: usize) {
+        T::upload_slice(location, &std::slice::from_ref(self)[..count]);
+    }
+}
+
+impl<T: UniformValue> Uniform for &[T] {
+    fn accepts(&self, gl_type: GLenum) -> bool {
+        T::accepts(gl_type)
+    }
+    fn element_count(&self) -> usize {
+        self.len()
+    }
+    unsafe fn upload(&self, location: c_int, count: usize) {
+        T::upload_slice(location, &self[..count]);
+    }
+}
+
+impl<T: UniformValue> Uniform for &Vec<T> {
+    fn accepts(&self, gl_type: GLenum) -> bool {
+        T::accepts(gl_type)
+    }
+    fn element_count(&self) -> usize {
+        self.len()
+    }
+    unsafe fn upload(&self, location: c_int, count: usize) {
+        T::upload_slice(location, &self[..count]);
+    }
+}
+
+impl<T: UniformValue, const N: usize> Uniform for [T; N] {
+    fn accepts(&self, gl_type: GLenum) -> bool {
+        T::accepts(gl_type)
+    }
+    fn element_count(&self) -> usize {
+        N
+    }
+    unsafe fn upload(&self, location: c_int, count: usize) {
+        T::upload_slice(location, &self[..count]);
+    }
+}
+
+impl<T: UniformValue, const N: usize> Uniform for &[T; N] {
+    fn accepts(&self, gl_type: GLenum) -> bool {
+        T::accepts(gl_type)
+    }
+    fn element_count(&self) -> usize {
+        N
+    }
+    unsafe fn upload(&self, location: c_int, count: usize) {
+        T::upload_slice(location, &self[..count]);
+    }
+}
+
+// cgmath vectors and matrices are #[repr(C)], a slice of them is a flat array of components
+macro_rules! impl_uniform_value {
+    ($value:ty, [$($gl_type:ident),+], $upload:ident) => {
+        impl UniformValue for $value {
+            fn accepts(gl_type: GLenum) -> bool {
+                [$($gl_type),+].contains(&gl_type)
+            }
+            unsafe fn upload_slice(location: c_int, values: &[Self]) {
+                $upload(location, values.len() as _, values.as_ptr().cast());
+            }
+        }
+    };
+}
+
+macro_rules! impl_uniform_matrix {
+    ($value:ty, $gl_type:ident, $upload:ident) => {
+        impl UniformValue for $value {
+            fn accepts(gl_type: GLenum) -> bool {
+                gl_type == $gl_type
+            }
+            unsafe fn upload_slice(location: c_int, values: &[Self]) {
+                // cgmath matrices are column major like GLSL
+                $upload(location, values.len() as _, GL_FALSE.0 as _, values.as_ptr().cast());
+            }
+        }
+    };
+}
+
+// bools are uploaded as ints, GLSL has no bool upload
+macro_rules! impl_uniform_bool {
+    ($value:ty, $gl_type:ident, $upload:ident, $to_ints:expr) => {
+        impl UniformValue for $value {
+            fn accepts(gl_type: GLenum) -> bool {
+                gl_type == $gl_type
+            }
+            unsafe fn upload_slice(location: c_int, values: &[Self]) {
+                let ints: Vec<i32> = values.iter().flat_map($to_ints).collect();
+                $upload(location, values.len() as _, ints.as_ptr());
+            }
+        }
+    };
+}
+
+impl_uniform_value!(f32, [GL_FLOAT], glUniform1fv);
+impl_uniform_value!(Vector2<f32>, [GL_FLOAT_VEC2], glUniform2fv);
+impl_uniform_value!(Vector3<f32>, [GL_FLOAT_VEC3], glUniform3fv);
+impl_uniform_value!(Vector4<f32>, [GL_FLOAT_VEC4], glUniform4fv);
+
+impl_uniform_value!(Vector2<i32>, [GL_INT_VEC2, GL_BOOL_VEC2], glUniform2iv);
+impl_uniform_value!(Vector3<i32>, [GL_INT_VEC3, GL_BOOL_VEC3], glUniform3iv);
+impl_uniform_value!(Vector4<i32>, [GL_INT_VEC4, GL_BOOL_VEC4], glUniform4iv);
+
+impl_uniform_value!(u32, [GL_UNSIGNED_INT, GL_BOOL], glUniform1uiv);
+impl_uniform_value!(Vector2<u32>, [GL_UNSIGNED_INT_VEC2, GL_BOOL_VEC2], glUniform2uiv);
+impl_uniform_value!(Vector3<u32>, [GL_UNSIGNED_INT_VEC3, GL_BOOL_VEC3], glUniform3uiv);
+impl_uniform_value!(Vector4<u32>, [GL_UNSIGNED_INT_VEC4, GL_BOOL_VEC4], glUniform4uiv);
+
+impl_uniform_matrix!(Matrix2<f32>, GL_FLOAT_MAT2, glUniformMatrix2fv);
+impl_uniform_matrix!(Matrix3<f32>, GL_FLOAT_MAT3, glUniformMatrix3fv);
+impl_uniform_matrix!(Matrix4<f32>, GL_FLOAT_MAT4, glUniformMatrix4fv);
+
+impl_uniform_bool!(bool, GL_BOOL, glUniform1iv, |value| [*value as i32]);
+impl_uniform_bool!(Vector2<bool>, GL_BOOL_VEC2, glUniform2iv, |value| [value.x as i32, value.y as i32]);
+impl_uniform_bool!(Vector3<bool>, GL_BOOL_VEC3, glUniform3iv, |value| [value.x as i32, value.y as i32, value.z as i32]);
+impl_uniform_bool!(Vector4<bool>, GL_BOOL_VEC4, glUniform4iv, |value| [value.x as i32, value.y as i32, value.z as i32, value.w as i32]);
+
+// ints also set bools and, like TextureUnit, samplers
+impl UniformValue for i32 {
+    fn accepts(gl_type: GLenum) -> bool {
+        gl_type == GL_INT || gl_type == GL_BOOL || SAMPLER_TYPES.contains(&gl_type)
+    }
+    unsafe fn upload_slice(location: c_int, values: &[Self]) {
+        glUniform1iv(location, values.len() as _, values.as_ptr());
+    }
+}
+
+impl UniformValue for TextureUnit {
+    fn accepts(gl_type: GLenum) -> bool {
+        SAMPLER_TYPES.contains(&gl_type)
+    }
+    unsafe fn upload_slice(location: c_int, values: &[Self]) {
+        let units: Vec<i32> = values.iter().map(|unit| unit.0 as i32).collect();
+        glUniform1iv(location, values.len() as _, units.as_ptr());
+    }
+}
@@ -0,0 +1,309 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::ops::Range;
+use std::os::raw::c_uint;
+
+use cgmath::{Matrix2, Matrix3, Matrix4, Vector2, Vector3, Vector4};
+use gl33::*;
+use gl33::global_loader::*;
+
+use crate::shader::ShaderReflection;
+
+thread_local! {
+    // binding point of every uniform block name, shared by all shaders and buffers of the context
+    static BLOCK_BINDINGS: RefCell<HashMap<String, u32>> = RefCell::new(HashMap::new());
+}
+
+/// A value that can be a member of a std140 uniform block.
+pub trait Std140Value {
+    // base alignment and size in bytes following the std140 rules
+    const ALIGN: usize;
+    const SIZE: usize;
+
+    /// Appends exactly `SIZE` bytes.
+    fn write_bytes(&self, bytes: &mut Vec<u8>);
+}
+
+/// A struct mirroring a GLSL `uniform` block.
+pub trait UniformBlock {
+    /// Writes the members in the order they are declared in GLSL.
+    fn write_std140(&self, writer: &mut Std140Writer);
+}
+
+/// Lays out uniform block members with std140 alignment.
+#[derive(Debug, Default)]
+pub struct Std140Writer {
+    bytes: Vec<u8>
+}
+
+impl Std140Writer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the offset of `value` in the block.
+    pub fn write<V: Std140Value>(&mut self, value: &V) -> usize {
+        let offset = self.align_to(V::ALIGN);
+        value.write_bytes(&mut self.bytes);
+        offset
+    }
+
+    /// Returns the offset of the first element, elements are padded to 16 bytes.
+    pub fn write_array<V: Std140Value>(&mut self, values: &[V]) -> usize {
+        let stride = round_up(V::SIZE, 16);
+        let offset = self.align_to(round_up(V::ALIGN, 16));
+        for value in values {
+            let start = self.bytes.len();
+            value.write_bytes(&mut self.bytes);
+            self.bytes.resize(start + stride, 0);
+        }
+        offset
+    }
+
+    pub fn into_bytes(mut self) -> Vec<u8> {
+        self.align_to(16);
+        self.bytes
+    }
+
+    fn align_to(&mut self, align: usize) -> usize {
+        let offset = round_up(self.bytes.len(), align);
+        self.bytes.resize(offset, 0);
+        offset
+    }
+}
+
+/// A uniform buffer holding one `UniformBlock`, bound to the binding point of its block name.
+///
+/// Every shader with a uniform block of that name reads from it, no matter if it was linked before or after.
+pub struct UniformBuffer<T: UniformBlock> {
+    buffer_id: c_uint,
+    binding: u32,
+    // what the buffer holds, so updates only upload the bytes that changed
+    bytes: Vec<u8>,
+    _block: PhantomData<T>
+}
+
+impl<T: UniformBlock> UniformBuffer<T> {
+    pub fn new(block_name: &str, value: &T) -> Self {
+        let mut buffer = UniformBuffer {
+            buffer_id: 0,
+            binding: uniform_block_binding(block_name),
+            bytes: to_std140(value),
+            _block: PhantomData
+        };
+        unsafe {
+            glGenBuffers(1, &mut buffer.buffer_id);
+            assert_ne!(buffer.buffer_id, 0);
+        }
+        buffer.allocate();
+        buffer.bind();
+        buffer
+    }
+
+    pub fn binding(&self) -> u32 {
+        self.binding
+    }
+
+    pub fn size(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Uploads the part of `value` that changed since the last update.
+    pub fn update(&mut self, value: &T) {
+        let bytes = to_std140(value);
+        if bytes.len() != self.bytes.len() {
+            // an array member changed its length
+            self.bytes = bytes;
+            self.allocate();
+            return;
+        }
+        if let Some(range) = changed_range(&self.bytes, &bytes) {
+            self.bytes = bytes;
+            self.upload_range(range);
+        }
+    }
+
+    /// Overwrites part of the block, `offset` as returned by `Std140Writer::write`.
+    pub fn update_bytes(&mut self, offset: usize, bytes: &[u8]) {
+        let range = offset..offset + bytes.len();
+        assert!(range.end <= self.bytes.len(), "Error: uniform buffer update out of bounds");
+        self.bytes[range.clone()].copy_from_slice(bytes);
+        self.upload_range(range);
+    }
+
+    /// Binds the buffer to its binding point again, e.g. after other code used it.
+    pub fn bind(&self) {
+        unsafe {
+            glBindBufferBase(GL_UNIFORM_BUFFER, self.binding, self.buffer_id);
+        }
+    }
+
+    fn allocate(&self) {
+        unsafe {
+            glBindBuffer(GL_UNIFORM_BUFFER, self.buffer_id);
+            glBufferData(GL_UNIFORM_BUFFER, self.bytes.len() as _, self.bytes.as_ptr().cast(), GL_DYNAMIC_DRAW);
+            glBindBuffer(GL_UNIFORM_BUFFER, 0);
+        }
+    }
+
+    fn upload_range(&self, range: Range<usize>) {
+        unsafe {
+            glBindBuffer(GL_UNIFORM_BUFFER, self.buffer_id);
+            glBufferSubData(GL_UNIFORM_BUFFER, range.start as _, range.len() as _, self.bytes[range].as_ptr().cast());
+            glBindBuffer(GL_UNIFORM_BUFFER, 0);
+        }
+    }
+}
+
+impl<T: UniformBlock> Drop for UniformBuffer<T> {
+    fn drop(&mut self) {
+        unsafe {
+            glDeleteBuffers(1, &self.buffer_id);
+        }
+    }
+}
+
+/// Binding point of the uniform block `block_name`, assigned the first time the name shows up.
+pub fn uniform_block_binding(block_name: &str) -> u32 {
+    BLOCK_BINDINGS.with(|bindings| {
+        let mut bindings = bindings.borrow_mut();
+        if let Some(binding) = bindings.get(block_name) {
+            return *binding;
+        }
+        let binding = bindings.len() as u32;
+        let mut max_bindings = 0;
+        unsafe {
+            glGetIntegerv(GL_MAX_UNIFORM_BUFFER_BINDINGS, &mut max_bindings);
+        }
+        assert!(binding < max_bindings as u32, "Error: more than {} uniform block names", max_bindings);
+        bindings.insert(block_name.to_string(), binding);
+        binding
+    })
+}
+
+// points every uniform block of a freshly linked program at the binding point of its name
+pub(crate) fn bind_uniform_blocks(program_id: c_uint, reflection: &ShaderReflection) {
+    for block in reflection.uniform_blocks() {
+        unsafe {
+            glUniformBlockBinding(program_id, block.index, block.binding);
+        }
+    }
+}
+
+fn to_std140<T: UniformBlock>(value: &T) -> Vec<u8> {
+    let mut writer = Std140Writer::new();
+    value.write_std140(&mut writer);
+    writer.into_bytes()
+}
+
+fn changed_range(old: &[u8], new: &[u8]) -> Option<Range<usize>> {
+    let start = old.iter().zip(new).position(|(old, new)| old != new)?;
+    let end = new.len() - old.iter().rev().zip(new.iter().rev()).position(|(old, new)| old != new)?;
+    Some(start..end)
+}
+
+fn round_up(value: usize, align: usize) -> usize {
+    value.div_ceil(align) * align
+}
+
+fn push_f32s(bytes: &mut Vec<u8>, values: &[f32]) {
+    values.iter().for_each(|value| bytes.extend_from_slice(&value.to_ne_bytes()));
+}
+
+macro_rules! impl_std140_scalar {
+    ($value:ty) => {
+        impl Std140Value for $value {
+            const ALIGN: usize = 4;
+            const SIZE: usize = 4;
+            fn write_bytes(&self, bytes: &mut Vec<u8>) {
+                bytes.extend_from_slice(&self.to_ne_bytes());
+            }
+        }
+    };
+}
+
+// vec3 is aligned like a vec4 but only 12 bytes big, a following scalar fills the gap
+macro_rules! impl_std140_vector {
+    ($vector:ident, $value:ty, $align:expr, [$($field:ident),+]) => {
+        impl Std140Value for $vector<$value> {
+            const ALIGN: usize = $align;
+            const SIZE: usize = 4 * [$(stringify!($field)),+].len();
+            fn write_bytes(&self, bytes: &mut Vec<u8>) {
+                $(bytes.extend_from_slice(&self.$field.to_ne_bytes());)+
+            }
+        }
+    };
+}
+
+// column major, every column is padded to a vec4
+macro_rules! impl_std140_matrix {
+    ($matrix:ident, [$($column:ident),+], $column_len:expr) => {
+        impl Std140Value for $matrix<f32> {
+            const ALIGN: usize = 16;
+            const SIZE: usize = 16 * [$(stringify!($column)),+].len();
+            fn write_bytes(&self, bytes: &mut Vec<u8>) {
+                $(
+                    let column: &[f32; $column_len] = self.$column.as_ref();
+                    push_f32s(bytes, column);
+                    push_f32s(bytes, &[0.0; 4][$column_len..]);
+                )+
+            }
+        }
+    };
+}
+
+impl_std140_scalar!(f32);
+impl_std140_scalar!(i32);
+impl_std140_scalar!(u32);
+
+impl Std140Value for bool {
+    const ALIGN: usize = 4;
+    const SIZE: usize = 4;
+    fn write_bytes(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&(*self as u32).to_ne_bytes());
+    }
+}
+
+impl_std140_vector!(Vector2, f32, 8, [x, y]);
+impl_std140_vector!(Vector3, f32, 16, [x, y, z]);
+impl_std140_vector!(Vector4, f32, 16, [x, y, z, w]);
+impl_std140_vector!(Vector2, i32, 8, [x, y]);
+impl_std140_vector!(Vector3, i32, 16, [x, y, z]);
+impl_std140_vector!(Vector4, i32, 16, [x, y, z, w]);
+impl_std140_vector!(Vector2, u32, 8, [x, y]);
+impl_std140_vector!(Vector3, u32, 16, [x, y, z]);
+impl_std140_vector!(Vector4, u32, 16, [x, y, z, w]);
+
+impl_std140_matrix!(Matrix2, [x, y], 2);
+impl_std140_matrix!(Matrix3, [x, y, z], 3);
+impl_std140_matrix!(Matrix4, [x, y, z, w], 4);
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{Matrix3, Vector2, Vector3};
+
+    use super::{changed_range, Std140Writer};
+
+    #[test]
+    fn lays_out_members_with_std140_alignment() {
+        let mut writer = Std140Writer::new();
+        assert_eq!(writer.write(&1.0f32), 0);
+        assert_eq!(writer.write(&Vector3::new(1.0f32, 2.0, 3.0)), 16);
+        // fills the end of the vec3
+        assert_eq!(writer.write(&4u32), 28);
+        assert_eq!(writer.write(&Vector2::new(1.0f32, 2.0)), 32);
+        assert_eq!(writer.write(&Matrix3::from_scale(1.0f32)), 48);
+        // a mat3 takes three padded columns
+        assert_eq!(writer.write_array(&[1.0f32, 2.0, 3.0]), 96);
+        assert_eq!(writer.write(&true), 144);
+        assert_eq!(writer.into_bytes().len(), 160);
+    }
+
+    #[test]
+    fn finds_the_changed_byte_range() {
+        assert_eq!(changed_range(&[0, 1, 2, 3], &[0, 1, 2, 3]), None);
+        assert_eq!(changed_range(&[0, 1, 2, 3], &[0, 9, 9, 3]), Some(1..3));
+        assert_eq!(changed_range(&[0, 1, 2, 3], &[9, 1, 2, 9]), Some(0..4));
+    }
+}
@@ -9,6 +9,12 @@ pub struct DeltaTimer {
     elapsed: Cell<f64>
 }
 
+impl Default for DeltaTimer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl DeltaTimer {
     pub fn new() -> Self {
         unsafe {
@@ -8,31 +8,8 @@ use std::{mem, ptr, str};
 
 use std::ffi::{c_uint, CString};
 
-#[allow(unconditional_panic)]
-const fn illegal_null_in_string() {
-    [][0]
-}
-
-#[doc(hidden)]
-pub const fn validate_cstr_contents(bytes: &[u8]) {
-    let mut i = 0;
-    while i < bytes.len() {
-        if bytes[i] == b'\0' {
-            illegal_null_in_string();
-        }
-        i += 1;
-    }
-}
-
-macro_rules! cstr {
-    ( $s:literal ) => {{
-        validate_cstr_contents($s.as_bytes());
-        unsafe { std::mem::transmute::<_, &std::ffi::CStr>(concat!($s, "\0")) }
-    }};
-}
-
 fn compile_shader(src: &str, ty: GLenum) -> c_uint {
-    let shader = unsafe { glCreateShader(ty) };
+    let shader = glCreateShader(ty);
 
     let c_str = CString::new(src.as_bytes()).unwrap();
     unsafe {
@@ -64,13 +41,11 @@ fn compile_shader(src: &str, ty: GLenum) -> c_uint {
 }
 
 fn link_program(vs: c_uint, fs: c_uint) -> c_uint {
-    let program = unsafe { glCreateProgram() };
+    let program = glCreateProgram();
 
-    unsafe {
-        glAttachShader(program, vs);
-        glAttachShader(program, fs);
-        glLinkProgram(program);
-    }
+    glAttachShader(program, vs);
+    glAttachShader(program, fs);
+    glLinkProgram(program);
 
     let mut status = 0;
     unsafe {
@@ -148,15 +123,12 @@ impl Triangle {
             glBindVertexArray(self.vao);
 
             glBindBuffer(GL_ARRAY_BUFFER, self.vbo);
-            glBufferData(GL_ARRAY_BUFFER, (VERTEX_DATA.len() * mem::size_of::<f32>()) as _, mem::transmute(&VERTEX_DATA[0]), GL_STATIC_DRAW, );
+            glBufferData(GL_ARRAY_BUFFER, (VERTEX_DATA.len() * mem::size_of::<f32>()) as _, VERTEX_DATA.as_ptr().cast(), GL_STATIC_DRAW, );
             glUseProgram(self.program);
 
-            let c_out_color = cstr!("out_color");
-
-            glBindFragDataLocation(self.program, 0, c_out_color.as_ptr().cast());
+            glBindFragDataLocation(self.program, 0, c"out_color".as_ptr().cast());
 
-            let c_position = cstr!("position");
-            let pos_attr = glGetAttribLocation(self.program, c_position.as_ptr().cast());
+            let pos_attr = glGetAttribLocation(self.program, c"position".as_ptr().cast());
 
             glEnableVertexAttribArray(pos_attr as _);
             glVertexAttribPointer(pos_attr as _, 2, GL_FLOAT, GL_FALSE.0 as _, 0, ptr::null(), );
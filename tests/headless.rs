@@ -0,0 +1,79 @@
+// Needs an OpenGL 3.3 driver and a display, e.g. in CI:
+// LIBGL_ALWAYS_SOFTWARE=1 xvfb-run -a cargo test --test headless -- --ignored
+// UPDATE_GOLDEN=1 writes the rendered frame as the new golden image instead of comparing.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+
+use egui::{Color32, Frame, Rect, pos2, vec2};
+use egui_glfw_gl2::gui::GuiContext;
+use egui_glfw_gl2::headless::HeadlessWindow;
+
+const WIDTH: u32 = 160;
+const HEIGHT: u32 = 64;
+// per channel, for rasterizers that round slightly differently
+const TOLERANCE: u8 = 2;
+
+fn golden_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden/headless_label.png")
+}
+
+fn known_ui(gui_ctx: &mut GuiContext) {
+    egui::CentralPanel::default().frame(Frame::none()).show(&gui_ctx.egui_ctx, |ui| {
+        ui.painter().rect_filled(Rect::from_min_size(pos2(0.0, 0.0), vec2(WIDTH as f32, 16.0)), 0.0, Color32::RED);
+        ui.add_space(24.0);
+        ui.label("Hello headless");
+    });
+}
+
+fn read_png(path: &PathBuf) -> (u32, u32, Vec<u8>) {
+    let file = File::open(path).unwrap_or_else(|err| panic!("{}: {}, run with UPDATE_GOLDEN=1 to create it", path.display(), err));
+    let mut reader = png::Decoder::new(file).read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).unwrap();
+    assert_eq!((info.color_type, info.bit_depth), (png::ColorType::Rgba, png::BitDepth::Eight), "{} is not 8 bit RGBA", path.display());
+    pixels.truncate(info.buffer_size());
+    (info.width, info.height, pixels)
+}
+
+fn write_png(path: &PathBuf, width: u32, height: u32, pixels: &[u8]) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    let mut encoder = png::Encoder::new(BufWriter::new(File::create(path).unwrap()), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header().unwrap().write_image_data(pixels).unwrap();
+}
+
+#[test]
+#[ignore = "needs an OpenGL driver and a display"]
+fn renders_a_known_ui_like_the_golden_image() {
+    let mut headless = HeadlessWindow::new(WIDTH, HEIGHT, 1.0);
+    // the first frame only uploads the font atlas on some drivers, compare the second
+    headless.render(0.0, known_ui);
+    let pixels = headless.render(0.1, known_ui);
+    assert_eq!(pixels.len(), (WIDTH * HEIGHT * 4) as usize);
+
+    // rows come top first: the red bar is at the top, the bottom row is the black clear color
+    let row_len = (WIDTH * 4) as usize;
+    assert_eq!(&pixels[..4], &[255, 0, 0, 255]);
+    assert_eq!(&pixels[pixels.len() - row_len..][..4], &[0, 0, 0, 255]);
+
+    let path = golden_path();
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        write_png(&path, WIDTH, HEIGHT, &pixels);
+        return;
+    }
+    let (width, height, golden) = read_png(&path);
+    assert_eq!((width, height), (WIDTH, HEIGHT), "golden image size");
+    let different = pixels
+        .chunks_exact(4)
+        .zip(golden.chunks_exact(4))
+        .filter(|(pixel, expected)| pixel.iter().zip(expected.iter()).any(|(a, b)| a.abs_diff(*b) > TOLERANCE))
+        .count();
+    if different > 0 {
+        let actual_path = path.with_file_name("headless_label.actual.png");
+        write_png(&actual_path, WIDTH, HEIGHT, &pixels);
+        panic!("{} pixels differ from {}, the frame was written to {}", different, path.display(), actual_path.display());
+    }
+}